@@ -0,0 +1,406 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeInclusive;
+
+use dtchat_backend::{
+    dtchat::{Peer, Room},
+    message::{ChatMessage, MessageStatus},
+    time::DTChatTime,
+};
+use eframe::egui::{self, Color32, ComboBox, RichText, ScrollArea};
+use egui_plot::{AxisHints, Bar, BarChart, GridMark, Legend, Plot, PlotPoints, Points};
+
+use crate::utils::{
+    font::{pretty_duration, PrettyStr},
+    stats::{bin_width, histogram, Summary},
+};
+
+const HISTOGRAM_BINS: usize = 20;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum GroupBy {
+    Peer,
+    Protocol,
+    Room,
+}
+
+impl GroupBy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GroupBy::Peer => "Peer",
+            GroupBy::Protocol => "Protocol",
+            GroupBy::Room => "Room",
+        }
+    }
+}
+
+/// Only the messages sent during the window are taken into account
+#[derive(PartialEq, Clone, Copy)]
+pub enum TimeWindow {
+    LastMinutes(i64),
+    All,
+}
+
+impl TimeWindow {
+    pub fn name(&self) -> String {
+        match self {
+            TimeWindow::LastMinutes(minutes) if minutes % 60 == 0 => {
+                format!("Last {} h", minutes / 60)
+            }
+            TimeWindow::LastMinutes(minutes) => format!("Last {} min", minutes),
+            TimeWindow::All => "All".to_string(),
+        }
+    }
+
+    fn contains(&self, msg: &ChatMessage, now: &DTChatTime) -> bool {
+        match self {
+            TimeWindow::LastMinutes(minutes) => {
+                msg.send_time.timestamp_millis() >= now.timestamp_millis() - minutes * 60_000
+            }
+            TimeWindow::All => true,
+        }
+    }
+}
+
+fn group_key(
+    msg: &ChatMessage,
+    group_by: GroupBy,
+    local_peer: &Peer,
+    other_peers: &HashMap<String, Peer>,
+    rooms: &HashMap<String, Room>,
+) -> String {
+    match group_by {
+        // the other end: the sender of a received message, the destination of a sent one
+        GroupBy::Peer if msg.sender_uuid == local_peer.uuid => {
+            match (other_peers.get(&msg.room_uuid), rooms.get(&msg.room_uuid)) {
+                (Some(peer), _) => peer.name.clone(),
+                (None, Some(room)) => format!("{} (room)", room.name),
+                (None, None) => msg.room_uuid.clone(),
+            }
+        }
+        GroupBy::Peer => match other_peers.get(&msg.sender_uuid) {
+            Some(peer) => peer.name.clone(),
+            None => msg.sender_uuid.clone(),
+        },
+        GroupBy::Protocol => msg.source_endpoint.proto.to_pretty_str(),
+        GroupBy::Room => match rooms.get(&msg.room_uuid) {
+            Some(room) => room.name.clone(),
+            None => "direct".to_string(),
+        },
+    }
+}
+
+/// Prediction error in seconds (positive: later than predicted) and receive time in ms
+pub fn prediction_error(msg: &ChatMessage) -> Option<(f64, i64)> {
+    let received = msg.receive_time?.timestamp_millis();
+    let predicted = msg.predicted_arrival_time?.timestamp_millis();
+    Some(((received - predicted) as f64 / 1000.0, received))
+}
+
+/// receive time - send time, in seconds
+pub fn one_way_delay(msg: &ChatMessage) -> Option<f64> {
+    let received = msg.receive_time?.timestamp_millis();
+    Some((received - msg.send_time.timestamp_millis()) as f64 / 1000.0)
+}
+
+/// send completed - send time, in seconds
+pub fn hand_off_time(msg: &ChatMessage) -> Option<f64> {
+    let completed = msg.send_completed?.timestamp_millis();
+    Some((completed - msg.send_time.timestamp_millis()) as f64 / 1000.0)
+}
+
+#[derive(Default)]
+struct LatencySamples {
+    messages: usize,
+    failed: usize,
+    delays: Vec<f64>,
+    hand_offs: Vec<f64>,
+}
+
+fn seconds_str(secs: f64) -> String {
+    pretty_duration((secs * 1000.0).round() as i64)
+}
+
+pub struct AnalyticsView {
+    group_by: GroupBy,
+    window: TimeWindow,
+}
+
+impl AnalyticsView {
+    pub fn new() -> Self {
+        Self {
+            group_by: GroupBy::Peer,
+            window: TimeWindow::All,
+        }
+    }
+
+    fn show_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Group by:");
+            for group_by in [GroupBy::Peer, GroupBy::Protocol, GroupBy::Room] {
+                ui.selectable_value(&mut self.group_by, group_by, group_by.name());
+            }
+            ui.separator();
+            ui.label("Messages sent:");
+            ComboBox::from_id_salt("analytics_window")
+                .selected_text(self.window.name())
+                .show_ui(ui, |ui| {
+                    for window in [
+                        TimeWindow::LastMinutes(15),
+                        TimeWindow::LastMinutes(60),
+                        TimeWindow::LastMinutes(6 * 60),
+                        TimeWindow::LastMinutes(24 * 60),
+                        TimeWindow::All,
+                    ] {
+                        ui.selectable_value(&mut self.window, window, window.name());
+                    }
+                });
+        });
+    }
+
+    fn show_summary_grid(ui: &mut egui::Ui, id: &str, rows: &[(String, Summary)]) {
+        egui::Grid::new(id)
+            .striped(true)
+            .num_columns(8)
+            .show(ui, |ui| {
+                for header in ["", "count", "mean", "p50", "p90", "p99", "min", "max"] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                for (name, summary) in rows {
+                    ui.label(name);
+                    ui.label(summary.count.to_string());
+                    for value in [
+                        summary.mean,
+                        summary.p50,
+                        summary.p90,
+                        summary.p99,
+                        summary.min,
+                        summary.max,
+                    ] {
+                        ui.label(seconds_str(value));
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        messages: &[ChatMessage],
+        local_peer: &Peer,
+        other_peers: &HashMap<String, Peer>,
+        rooms: &HashMap<String, Room>,
+        current_time: &DTChatTime,
+    ) {
+        ui.add_space(8.0);
+        self.show_settings(ui);
+        ui.separator();
+
+        let messages: Vec<ChatMessage> = messages
+            .iter()
+            .filter(|msg| self.window.contains(msg, current_time))
+            .cloned()
+            .collect();
+
+        ScrollArea::vertical()
+            .id_salt("analytics_scroll")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                self.show_latency(ui, &messages, local_peer, other_peers, rooms);
+                ui.separator();
+                self.show_prediction_accuracy(ui, &messages, local_peer, other_peers, rooms);
+            });
+    }
+
+    fn show_latency(
+        &self,
+        ui: &mut egui::Ui,
+        messages: &[ChatMessage],
+        local_peer: &Peer,
+        other_peers: &HashMap<String, Peer>,
+        rooms: &HashMap<String, Room>,
+    ) {
+        ui.heading("⏱ Latency");
+        ui.label(
+            RichText::new(
+                "one-way delay: receive time - send time, hand-off: send completed - send time",
+            )
+            .color(Color32::GRAY),
+        );
+        if messages.is_empty() {
+            ui.colored_label(Color32::GRAY, "No message in this window");
+            return;
+        }
+
+        let mut all = LatencySamples::default();
+        let mut groups: BTreeMap<String, LatencySamples> = BTreeMap::new();
+        for msg in messages {
+            let key = group_key(msg, self.group_by, local_peer, other_peers, rooms);
+            for samples in [&mut all, groups.entry(key).or_default()] {
+                samples.messages += 1;
+                if msg.status == MessageStatus::Failed {
+                    samples.failed += 1;
+                }
+                samples.delays.extend(one_way_delay(msg));
+                samples.hand_offs.extend(hand_off_time(msg));
+            }
+        }
+
+        let optional = |value: Option<f64>| value.map_or("-".to_string(), seconds_str);
+        egui::Grid::new("latency_summary")
+            .striped(true)
+            .num_columns(9)
+            .show(ui, |ui| {
+                for header in [
+                    "",
+                    "messages",
+                    "failed",
+                    "delay mean",
+                    "delay p50",
+                    "delay p90",
+                    "delay p99",
+                    "hand-off mean",
+                    "hand-off p90",
+                ] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                for (name, samples) in std::iter::once(("All", &all))
+                    .chain(groups.iter().map(|(name, s)| (name.as_str(), s)))
+                {
+                    let delay = Summary::of(&samples.delays);
+                    let hand_off = Summary::of(&samples.hand_offs);
+                    ui.label(name);
+                    ui.label(samples.messages.to_string());
+                    let failure_rate = samples.failed as f64 * 100.0 / samples.messages as f64;
+                    let failed = format!("{} ({:.1} %)", samples.failed, failure_rate);
+                    if samples.failed > 0 {
+                        ui.colored_label(Color32::RED, failed);
+                    } else {
+                        ui.label(failed);
+                    }
+                    ui.label(optional(delay.as_ref().map(|s| s.mean)));
+                    ui.label(optional(delay.as_ref().map(|s| s.p50)));
+                    ui.label(optional(delay.as_ref().map(|s| s.p90)));
+                    ui.label(optional(delay.as_ref().map(|s| s.p99)));
+                    ui.label(optional(hand_off.as_ref().map(|s| s.mean)));
+                    ui.label(optional(hand_off.as_ref().map(|s| s.p90)));
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn show_prediction_accuracy(
+        &self,
+        ui: &mut egui::Ui,
+        messages: &[ChatMessage],
+        local_peer: &Peer,
+        other_peers: &HashMap<String, Peer>,
+        rooms: &HashMap<String, Room>,
+    ) {
+        ui.heading("🔭 Prediction accuracy (A-SABR)");
+        ui.label(
+            RichText::new("receive time - predicted arrival time, positive when late")
+                .color(Color32::GRAY),
+        );
+
+        // group -> (error in s, receive time in ms)
+        let mut groups: BTreeMap<String, Vec<(f64, i64)>> = BTreeMap::new();
+        for msg in messages {
+            if let Some(sample) = prediction_error(msg) {
+                let key = group_key(msg, self.group_by, local_peer, other_peers, rooms);
+                groups.entry(key).or_default().push(sample);
+            }
+        }
+        let all: Vec<f64> = groups.values().flatten().map(|(err, _)| *err).collect();
+        let Some(overall) = Summary::of(&all) else {
+            ui.colored_label(
+                Color32::GRAY,
+                "No delivered message with a predicted arrival time",
+            );
+            return;
+        };
+
+        let mut rows = vec![("All".to_string(), overall.clone())];
+        for (name, samples) in &groups {
+            let errors: Vec<f64> = samples.iter().map(|(err, _)| *err).collect();
+            if let Some(summary) = Summary::of(&errors) {
+                rows.push((name.clone(), summary));
+            }
+        }
+        Self::show_summary_grid(ui, "prediction_summary", &rows);
+        ui.add_space(8.0);
+
+        let plot_height = 260.0;
+        ui.columns(2, |columns| {
+            columns[0].label("Distribution");
+            let width = bin_width(overall.min, overall.max, HISTOGRAM_BINS);
+            // bars of the groups side by side in each bin
+            let bar_width = width / groups.len() as f64;
+            Plot::new("prediction_histogram")
+                .legend(Legend::default().position(egui_plot::Corner::RightTop))
+                .height(plot_height)
+                .x_axis_label("error (s)")
+                .y_axis_label("messages")
+                .show(&mut columns[0], |plot_ui| {
+                    for (idx, (name, samples)) in groups.iter().enumerate() {
+                        let errors: Vec<f64> = samples.iter().map(|(err, _)| *err).collect();
+                        let bars: Vec<Bar> =
+                            histogram(&errors, overall.min, overall.max, HISTOGRAM_BINS)
+                                .into_iter()
+                                .filter(|(_, count)| *count > 0)
+                                .map(|(start, count)| {
+                                    Bar::new(start + (idx as f64 + 0.5) * bar_width, count as f64)
+                                        .width(bar_width)
+                                        .name(format!(
+                                            "{}\n{} .. {}",
+                                            name,
+                                            seconds_str(start),
+                                            seconds_str(start + width)
+                                        ))
+                                })
+                                .collect();
+                        plot_ui.bar_chart(BarChart::new(bars).name(name));
+                    }
+                });
+
+            columns[1].label("Over time");
+            let x_axes =
+                vec![
+                    AxisHints::new_x().formatter(|x: GridMark, _range: &RangeInclusive<f64>| {
+                        match DTChatTime::from_timestamp_millis(x.value as i64) {
+                            Some(time) => time.ts_to_str(false, true, None, &chrono::Local),
+                            None => String::new(),
+                        }
+                    }),
+                ];
+            Plot::new("prediction_time_series")
+                .legend(Legend::default().position(egui_plot::Corner::RightTop))
+                .height(plot_height)
+                .custom_x_axes(x_axes)
+                .y_axis_label("error (s)")
+                .label_formatter(|name, value| {
+                    let time = DTChatTime::from_timestamp_millis(value.x as i64)
+                        .map(|t| t.ts_to_str(false, true, None, &chrono::Local))
+                        .unwrap_or_default();
+                    format!("{}\n{}\n{}", name, time, seconds_str(value.y))
+                })
+                .show(&mut columns[1], |plot_ui| {
+                    for (name, samples) in &groups {
+                        let points: PlotPoints = samples
+                            .iter()
+                            .map(|(err, received)| [*received as f64, *err])
+                            .collect();
+                        plot_ui.points(Points::new(points).radius(3.0).name(name));
+                    }
+                });
+        });
+    }
+}
+
+impl Default for AnalyticsView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
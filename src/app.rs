@@ -1,31 +1,72 @@
+use crate::elog_with_location;
 use crate::main_view::MainView;
+use crate::utils::correlation::{SocketEventKind, SocketFailure, SocketRecord};
 use crate::utils::font::PrettyStr;
+use crate::utils::history::{StatusTransition, TransitionKind};
+use crate::utils::recorder::{EventRecorder, RecordedEvent};
 use crate::utils::uuid::safe_id_display;
-use dtchat_backend::dtchat::ChatModel;
-use dtchat_backend::event::{
-    AppEventObserver, ChatAppErrorEvent, ChatAppEvent, ChatAppInfoEvent,
-    ErrorEvent::{ConnectionFailed, ReceiveFailed, SendFailed, SocketError},
-    NetworkErrorEvent, NetworkEvent,
-};
-use dtchat_backend::event::{ConnectionEvent, DataEvent};
+use dtchat_backend::dtchat::{ChatModel, Peer};
+use dtchat_backend::event::{AppEventObserver, ChatAppEvent};
+use dtchat_backend::message::ChatMessage;
 use dtchat_backend::time::DTChatTime;
+use dtchat_backend::EndpointProto;
 use eframe::{egui, App};
 use egui::{CentralPanel, Color32};
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EventLevel {
     Info,
     Debug,
     Error,
 }
 
+impl EventLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventLevel::Info => "Info",
+            EventLevel::Debug => "Debug",
+            EventLevel::Error => "Error",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct DisplayEvent {
-    level: EventLevel,
-    message: String,
-    timestamp: DTChatTime,
+    pub level: EventLevel,
+    pub message: String,
+    pub timestamp: DTChatTime,
+    // what the event is about, when known
+    pub peer_uuid: Option<String>,
+    pub endpoint: Option<String>,
+    pub protocol: Option<EndpointProto>,
+    pub message_uuid: Option<String>,
+    // token of a socket event, the message uuid when it is the uuid of a mirrored message
+    pub token: Option<String>,
+    pub bytes: Option<usize>,
+}
+
+/// Protocol written in the endpoint and the address without it: "udp://127.0.0.1:7000",
+/// "tcp 127.0.0.1:7000" and "ipn:2.1" (a bundle endpoint id) are recognized
+fn parse_endpoint(endpoint: &str) -> (Option<EndpointProto>, String) {
+    let endpoint = endpoint.trim().trim_end_matches('/').to_lowercase();
+    for (prefix, proto) in [
+        ("udp", EndpointProto::Udp),
+        ("tcp", EndpointProto::Tcp),
+        ("bp", EndpointProto::Bp),
+    ] {
+        for separator in ["://", " "] {
+            if let Some(address) = endpoint.strip_prefix(&format!("{}{}", prefix, separator)) {
+                return (Some(proto), address.trim().to_string());
+            }
+        }
+    }
+    if endpoint.starts_with("ipn:") || endpoint.starts_with("dtn:") {
+        return (Some(EndpointProto::Bp), endpoint);
+    }
+    (None, endpoint)
 }
 
 impl DisplayEvent {
@@ -34,8 +75,65 @@ impl DisplayEvent {
             level,
             message,
             timestamp: DTChatTime::now(),
+            peer_uuid: None,
+            endpoint: None,
+            protocol: None,
+            message_uuid: None,
+            token: None,
+            bytes: None,
         }
     }
+    pub fn with_peer(mut self, peer_uuid: String) -> Self {
+        self.peer_uuid = Some(peer_uuid);
+        self
+    }
+    pub fn with_endpoint(mut self, endpoint: String) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+    pub fn with_protocol(mut self, protocol: EndpointProto) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+    pub fn with_message(mut self, message_uuid: Option<String>) -> Self {
+        self.message_uuid = message_uuid;
+        self
+    }
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+    pub fn with_bytes(mut self, bytes: usize) -> Self {
+        self.bytes = Some(bytes);
+        self
+    }
+
+    /// Fills the peer and the protocol from the endpoint, using the endpoints
+    /// the peers advertise. The addresses must be equal once normalized, and of the
+    /// protocol of the event when it is known.
+    pub fn resolve(&mut self, local_peer: &Peer, other_peers: &HashMap<String, Peer>) {
+        let Some(endpoint) = &self.endpoint else {
+            return;
+        };
+        let (written_proto, address) = parse_endpoint(endpoint);
+        let proto = self.protocol.clone().or(written_proto);
+        for peer in std::iter::once(local_peer).chain(other_peers.values()) {
+            let matching = peer.endpoints.iter().find(|e| {
+                let (_, peer_address) = parse_endpoint(&e.endpoint);
+                !peer_address.is_empty()
+                    && peer_address == address
+                    && proto.as_ref().is_none_or(|proto| *proto == e.proto)
+            });
+            if let Some(peer_endpoint) = matching {
+                if self.peer_uuid.is_none() {
+                    self.peer_uuid = Some(peer.uuid.clone());
+                }
+                self.protocol = Some(peer_endpoint.proto.clone());
+                return;
+            }
+        }
+        self.protocol = proto;
+    }
     pub fn get_color(&self) -> Color32 {
         match self.level {
             EventLevel::Error => Color32::RED,
@@ -55,11 +153,45 @@ impl PrettyStr for DisplayEvent {
     }
 }
 
+/// What the UI consumes from the handler each time it refreshes its mirror of the model
+pub struct HandlerUpdate {
+    pub app_events: VecDeque<DisplayEvent>,
+    pub network_events: VecDeque<DisplayEvent>,
+    pub message_transitions: Vec<(String, StatusTransition)>,
+    pub socket_records: Vec<SocketRecord>,
+    pub socket_failures: Vec<SocketFailure>,
+    // replay mode only, the messages added or updated by the recorded events since the
+    // last update
+    pub replayed_messages: Option<Vec<ChatMessage>>,
+}
+
+impl HandlerUpdate {
+    /// Date of the latest thing in the update, the failures of the update happened then
+    pub fn latest_timestamp(&self) -> Option<DTChatTime> {
+        let events = self.app_events.iter().chain(self.network_events.iter());
+        events
+            .map(|event| event.timestamp)
+            .chain(self.message_transitions.iter().map(|(_, t)| t.timestamp))
+            .chain(self.socket_records.iter().map(|r| r.timestamp))
+            .max_by_key(|timestamp| timestamp.timestamp_millis())
+    }
+}
+
 pub struct EventHandler {
     pub network_events: VecDeque<DisplayEvent>,
     pub app_events: VecDeque<DisplayEvent>,
+    pub message_transitions: Vec<(String, StatusTransition)>,
+    pub socket_records: Vec<SocketRecord>,
+    pub socket_failures: Vec<SocketFailure>,
+    pub recorder: Option<EventRecorder>,
+    // replay mode only, the messages changed since the last update
+    pub replayed_messages: Option<Vec<ChatMessage>>,
+    // message uuid -> index in the replayed messages
+    replayed_index: HashMap<String, usize>,
     pub max_events_per_category: usize,
     pub refresh_model_request: bool,
+    // recorded time of the event being replayed, given to what it produces
+    event_time: Option<DTChatTime>,
 }
 
 impl EventHandler {
@@ -67,13 +199,26 @@ impl EventHandler {
         Self {
             network_events: VecDeque::new(),
             app_events: VecDeque::new(),
+            message_transitions: Vec::new(),
+            socket_records: Vec::new(),
+            socket_failures: Vec::new(),
+            recorder: None,
+            replayed_messages: None,
+            replayed_index: HashMap::new(),
             max_events_per_category,
             refresh_model_request: true,
+            event_time: None,
         }
     }
 
-    pub fn add_network_event(&mut self, level: EventLevel, message: String) {
-        let event = DisplayEvent::new(level, message);
+    fn now(&self) -> DTChatTime {
+        self.event_time.unwrap_or_else(DTChatTime::now)
+    }
+
+    pub fn push_network_event(&mut self, mut event: DisplayEvent) {
+        if let Some(timestamp) = self.event_time {
+            event.timestamp = timestamp;
+        }
         self.network_events.push_back(event);
 
         if self.network_events.len() > self.max_events_per_category {
@@ -81,13 +226,70 @@ impl EventHandler {
         }
     }
     pub fn add_app_event(&mut self, level: EventLevel, message: String) {
-        let event = DisplayEvent::new(level, message);
+        self.push_app_event(DisplayEvent::new(level, message));
+    }
+    pub fn push_app_event(&mut self, mut event: DisplayEvent) {
+        if let Some(timestamp) = self.event_time {
+            event.timestamp = timestamp;
+        }
         self.app_events.push_back(event);
 
         if self.app_events.len() > self.max_events_per_category {
             self.app_events.pop_front();
         }
     }
+    pub fn record_transition(&mut self, msg_uuid: &str, kind: TransitionKind) {
+        let transition = StatusTransition::new(kind, self.now());
+        self.message_transitions
+            .push((msg_uuid.to_string(), transition));
+    }
+    pub fn consume_message_transitions(&mut self) -> Vec<(String, StatusTransition)> {
+        std::mem::take(&mut self.message_transitions)
+    }
+    /// Keeps a socket event, linked to its chat message when the UI refreshes
+    pub fn record_socket_event(
+        &mut self,
+        kind: SocketEventKind,
+        token: &str,
+        endpoint: String,
+        bytes: Option<usize>,
+        error: Option<String>,
+    ) {
+        let record = SocketRecord {
+            kind,
+            token: token.to_string(),
+            endpoint,
+            bytes,
+            error,
+            timestamp: self.now(),
+        };
+        self.socket_records.push(record);
+    }
+    /// Every failure is notified, with its message when the token is the uuid of one
+    fn push_socket_failure(
+        &mut self,
+        kind: SocketEventKind,
+        endpoint: &str,
+        reason: &str,
+        token: &str,
+    ) {
+        self.socket_failures.push(SocketFailure {
+            kind,
+            endpoint: endpoint.to_string(),
+            reason: reason.to_string(),
+            token: token.to_string(),
+        });
+    }
+    pub fn consume_update(&mut self) -> HandlerUpdate {
+        HandlerUpdate {
+            app_events: self.consume_app_events(),
+            network_events: self.consume_network_events(),
+            message_transitions: self.consume_message_transitions(),
+            socket_records: std::mem::take(&mut self.socket_records),
+            socket_failures: std::mem::take(&mut self.socket_failures),
+            replayed_messages: self.consume_replayed_messages(),
+        }
+    }
     pub fn consume_network_events(&mut self) -> VecDeque<DisplayEvent> {
         let res = self.network_events.clone();
         self.network_events.clear();
@@ -99,31 +301,82 @@ impl EventHandler {
         res
     }
 
+    /// The messages changed since the last call, the mirrored data keeps the others
+    fn consume_replayed_messages(&mut self) -> Option<Vec<ChatMessage>> {
+        self.replayed_index.clear();
+        self.replayed_messages.as_mut().map(std::mem::take)
+    }
+
+    /// Keeps the latest copy of each message carried by the events, used as the message
+    /// store when replaying a recording
+    fn mirror_message(&mut self, event: &RecordedEvent) {
+        let (Some(store), Some(msg)) = (self.replayed_messages.as_mut(), event.message()) else {
+            return;
+        };
+        match self.replayed_index.get(&msg.uuid) {
+            Some(idx) => store[*idx] = msg.clone(),
+            None => {
+                self.replayed_index.insert(msg.uuid.clone(), store.len());
+                store.push(msg.clone());
+            }
+        }
+    }
+
     pub fn handle_chat_app_event(&mut self, app_event: ChatAppEvent) {
-        match app_event {
-            ChatAppEvent::Message(info_event) => match info_event {
-                ChatAppInfoEvent::Sending(msg) => {
-                    self.add_app_event(
+        let event = RecordedEvent::from(app_event);
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(err) = recorder.record(&event) {
+                elog_with_location!("Unable to record event, recording stopped: {}", err);
+                self.recorder = None;
+            }
+        }
+        self.handle_event(event);
+    }
+
+    /// Handles a recorded event as if it happened at `timestamp`
+    pub fn handle_event_at(&mut self, event: RecordedEvent, timestamp: Option<DTChatTime>) {
+        self.event_time = timestamp;
+        self.handle_event(event);
+        self.event_time = None;
+    }
+
+    pub fn handle_event(&mut self, event: RecordedEvent) {
+        self.mirror_message(&event);
+        match event {
+            RecordedEvent::Sending(msg) => {
+                self.record_transition(&msg.uuid, TransitionKind::Sending);
+                self.push_app_event(
+                    DisplayEvent::new(
                         EventLevel::Info,
                         format!(
                             "Sending msg {} to room {}",
                             safe_id_display(&msg.uuid),
                             safe_id_display(&msg.room_uuid)
                         ),
-                    );
-                }
-                ChatAppInfoEvent::Sent(msg) => {
-                    self.add_app_event(
+                    )
+                    .with_protocol(msg.source_endpoint.proto.clone())
+                    .with_message(Some(msg.uuid.clone())),
+                );
+            }
+            RecordedEvent::Sent(msg) => {
+                self.record_transition(&msg.uuid, TransitionKind::Sent);
+                self.push_app_event(
+                    DisplayEvent::new(
                         EventLevel::Info,
                         format!(
                             "Sent msg {} to room {}",
                             safe_id_display(&msg.uuid),
                             safe_id_display(&msg.room_uuid)
                         ),
-                    );
-                }
-                ChatAppInfoEvent::Received(msg) => {
-                    self.add_app_event(
+                    )
+                    .with_protocol(msg.source_endpoint.proto.clone())
+                    .with_message(Some(msg.uuid.clone())),
+                );
+            }
+            RecordedEvent::Received(msg) => {
+                self.record_transition(&msg.uuid, TransitionKind::Received);
+                self.push_app_event(
+                    DisplayEvent::new(
                         EventLevel::Info,
                         format!(
                             "Received: msg {} from {} for room {}",
@@ -131,10 +384,17 @@ impl EventHandler {
                             safe_id_display(&msg.sender_uuid),
                             safe_id_display(&msg.room_uuid)
                         ),
-                    );
-                }
-                ChatAppInfoEvent::AckSent(msg, peer_id) => {
-                    self.add_app_event(
+                    )
+                    .with_peer(msg.sender_uuid.clone())
+                    .with_endpoint(msg.source_endpoint.endpoint.clone())
+                    .with_protocol(msg.source_endpoint.proto.clone())
+                    .with_message(Some(msg.uuid.clone())),
+                );
+            }
+            RecordedEvent::AckSent(msg, peer_id) => {
+                self.record_transition(&msg.uuid, TransitionKind::AckSent);
+                self.push_app_event(
+                    DisplayEvent::new(
                         EventLevel::Info,
                         format!(
                             "ACK sent for msg {} from room {} to {}",
@@ -142,148 +402,209 @@ impl EventHandler {
                             safe_id_display(&msg.room_uuid),
                             peer_id
                         ),
-                    );
-                }
-                ChatAppInfoEvent::AckReceived(msg) => {
-                    self.add_app_event(
+                    )
+                    .with_peer(peer_id)
+                    .with_message(Some(msg.uuid.clone())),
+                );
+            }
+            RecordedEvent::AckReceived(msg) => {
+                self.record_transition(&msg.uuid, TransitionKind::AckReceived);
+                self.push_app_event(
+                    DisplayEvent::new(
                         EventLevel::Info,
                         format!("ACK received for msg {}", safe_id_display(&msg.uuid)),
-                    );
-                }
-            },
-            ChatAppEvent::Error(error_event) => match error_event {
-                ChatAppErrorEvent::ProtocolDecode(error) => {
-                    self.add_app_event(EventLevel::Error, format!("Protocol decode: {}", error));
-                }
-                ChatAppErrorEvent::ProtocolEncode(error) => {
-                    self.add_app_event(EventLevel::Error, format!("Protocol encode: {}", error));
-                }
-                ChatAppErrorEvent::InvalidMessage(error) => {
-                    self.add_app_event(EventLevel::Error, format!("Invalid message: {}", error));
-                }
-                ChatAppErrorEvent::MessageNotFound(msg_id) => {
-                    self.add_app_event(
-                        EventLevel::Error,
-                        format!("Message not found: {}", safe_id_display(&msg_id)),
-                    );
-                }
-                ChatAppErrorEvent::PeerNotFound(peer_id) => {
-                    self.add_app_event(EventLevel::Error, format!("Peer not found: {}", peer_id));
-                }
-                ChatAppErrorEvent::NoEngineAttached => {
-                    self.add_app_event(EventLevel::Error, "No engine attached".to_string());
-                }
-                ChatAppErrorEvent::InternalError(error) => {
-                    self.add_app_event(EventLevel::Error, format!("Internal: {}", error));
-                }
-            },
-            ChatAppEvent::SocketEngineInfo(network_event) => {
-                let (level, event_text) = match network_event {
-                    NetworkEvent::Data(data_event) => match data_event {
-                        DataEvent::Received { data, from } => (
-                            EventLevel::Info,
-                            format!("Received {} bytes from {}", data.len(), from.to_string()),
-                        ),
-                        DataEvent::Sent {
-                            token,
+                    )
+                    .with_message(Some(msg.uuid.clone())),
+                );
+            }
+            RecordedEvent::ProtocolDecode(error) => {
+                self.add_app_event(EventLevel::Error, format!("Protocol decode: {}", error));
+            }
+            RecordedEvent::ProtocolEncode(error) => {
+                self.add_app_event(EventLevel::Error, format!("Protocol encode: {}", error));
+            }
+            RecordedEvent::InvalidMessage(error) => {
+                self.add_app_event(EventLevel::Error, format!("Invalid message: {}", error));
+            }
+            RecordedEvent::MessageNotFound(msg_id) => {
+                self.add_app_event(
+                    EventLevel::Error,
+                    format!("Message not found: {}", safe_id_display(&msg_id)),
+                );
+            }
+            RecordedEvent::PeerNotFound(peer_id) => {
+                self.add_app_event(EventLevel::Error, format!("Peer not found: {}", peer_id));
+            }
+            RecordedEvent::NoEngineAttached => {
+                self.add_app_event(EventLevel::Error, "No engine attached".to_string());
+            }
+            RecordedEvent::InternalError(error) => {
+                self.add_app_event(EventLevel::Error, format!("Internal: {}", error));
+            }
+            RecordedEvent::DataReceived { bytes, from } => {
+                self.push_network_event(
+                    DisplayEvent::new(
+                        EventLevel::Info,
+                        format!("Received {} bytes from {}", bytes, from),
+                    )
+                    .with_endpoint(from)
+                    .with_bytes(bytes),
+                );
+            }
+            RecordedEvent::DataSent { token, to, bytes } => {
+                self.record_socket_event(
+                    SocketEventKind::Sent,
+                    &token,
+                    to.clone(),
+                    Some(bytes),
+                    None,
+                );
+                self.push_network_event(
+                    DisplayEvent::new(
+                        EventLevel::Info,
+                        format!(
+                            "Sent {} bytes to {} (token: {})",
+                            bytes,
                             to,
-                            bytes_sent,
-                        } => (
-                            EventLevel::Info,
-                            format!(
-                                "Sent {} bytes to {} (token: {})",
-                                bytes_sent,
-                                to.to_string(),
-                                safe_id_display(&token)
-                            ),
-                        ),
-                        DataEvent::Sending { token, to, bytes } => (
-                            EventLevel::Info,
-                            format!(
-                                "Sending {} bytes to {} (token: {})",
-                                bytes,
-                                to.to_string(),
-                                safe_id_display(&token)
-                            ),
+                            safe_id_display(&token)
                         ),
-                    },
-                    NetworkEvent::Connection(connection_event) => match connection_event {
-                        // TODO: not working even from dtchat-backend and socket-engine
-                        ConnectionEvent::ListenerStarted { endpoint } => (
-                            EventLevel::Info,
-                            format!("Listening on {}", endpoint.to_string()),
+                    )
+                    .with_endpoint(to)
+                    .with_bytes(bytes)
+                    .with_token(token),
+                );
+            }
+            RecordedEvent::DataSending { token, to, bytes } => {
+                self.record_socket_event(
+                    SocketEventKind::Sending,
+                    &token,
+                    to.clone(),
+                    Some(bytes),
+                    None,
+                );
+                self.push_network_event(
+                    DisplayEvent::new(
+                        EventLevel::Info,
+                        format!(
+                            "Sending {} bytes to {} (token: {})",
+                            bytes,
+                            to,
+                            safe_id_display(&token)
                         ),
-                        ConnectionEvent::Established { remote } => {
-                            let client_addr = remote.endpoint;
-                            (
-                                EventLevel::Debug,
-                                format!("Connection established (client: {})", client_addr),
-                            )
-                        }
-                        ConnectionEvent::Closed { remote } => {
-                            let message = match remote {
-                                Some(remote_ep) => {
-                                    let client_addr = remote_ep.endpoint;
-                                    format!("Connection closed (client: {})", client_addr)
-                                }
-                                None => "Connection closed (no client info)".to_string(),
-                            };
-                            (EventLevel::Debug, message)
-                        }
-                    },
+                    )
+                    .with_endpoint(to)
+                    .with_bytes(bytes)
+                    .with_token(token),
+                );
+            }
+            // TODO: not working even from dtchat-backend and socket-engine
+            RecordedEvent::ListenerStarted { endpoint } => {
+                self.push_network_event(
+                    DisplayEvent::new(EventLevel::Info, format!("Listening on {}", endpoint))
+                        .with_endpoint(endpoint),
+                );
+            }
+            RecordedEvent::ConnectionEstablished { endpoint } => {
+                self.push_network_event(
+                    DisplayEvent::new(
+                        EventLevel::Debug,
+                        format!("Connection established (client: {})", endpoint),
+                    )
+                    .with_endpoint(endpoint),
+                );
+            }
+            RecordedEvent::ConnectionClosed { endpoint } => {
+                let event = match endpoint {
+                    Some(client_addr) => DisplayEvent::new(
+                        EventLevel::Debug,
+                        format!("Connection closed (client: {})", client_addr),
+                    )
+                    .with_endpoint(client_addr),
+                    None => DisplayEvent::new(
+                        EventLevel::Debug,
+                        "Connection closed (no client info)".to_string(),
+                    ),
                 };
-
-                self.add_network_event(level, event_text);
+                self.push_network_event(event);
             }
-            ChatAppEvent::SocketEngineError(network_error) => {
-                match network_error {
-                    NetworkErrorEvent::SocketError(socket_error) => match socket_error {
-                        ConnectionFailed {
-                            endpoint,
+            RecordedEvent::ConnectionFailed {
+                endpoint,
+                token,
+                reason,
+            } => {
+                self.record_socket_event(
+                    SocketEventKind::ConnectionFailed,
+                    &token,
+                    endpoint.clone(),
+                    None,
+                    Some(reason.clone()),
+                );
+                self.push_socket_failure(
+                    SocketEventKind::ConnectionFailed,
+                    &endpoint,
+                    &reason,
+                    &token,
+                );
+                self.push_network_event(
+                    DisplayEvent::new(
+                        EventLevel::Error,
+                        format!(
+                            "Connection failed: {} (endpoint: {}, token {})",
                             reason,
-                            token,
-                        } => {
-                            self.add_network_event(
-                                EventLevel::Error,
-                                format!(
-                                    "Connection failed: {:?} (endpoint: {}, token {})",
-                                    reason,
-                                    endpoint,
-                                    safe_id_display(&token)
-                                ),
-                            );
-                        }
-                        SendFailed {
                             endpoint,
-                            token,
+                            safe_id_display(&token)
+                        ),
+                    )
+                    .with_endpoint(endpoint)
+                    .with_token(token),
+                );
+            }
+            RecordedEvent::SendFailed {
+                endpoint,
+                token,
+                reason,
+            } => {
+                self.record_socket_event(
+                    SocketEventKind::SendFailed,
+                    &token,
+                    endpoint.clone(),
+                    None,
+                    Some(reason.clone()),
+                );
+                self.push_socket_failure(SocketEventKind::SendFailed, &endpoint, &reason, &token);
+                self.push_network_event(
+                    DisplayEvent::new(
+                        EventLevel::Error,
+                        format!(
+                            "Send failed: {} (endpoint: {}, token {})",
                             reason,
-                        } => {
-                            self.add_network_event(
-                                EventLevel::Error,
-                                format!(
-                                    "Send failed: {:?} (endpoint: {}, token {})",
-                                    reason,
-                                    endpoint,
-                                    safe_id_display(&token)
-                                ),
-                            );
-                        }
-                        ReceiveFailed { endpoint, reason } => {
-                            self.add_network_event(
-                                EventLevel::Error,
-                                format!("Receive failed: {:?} (endpoint: {})", reason, endpoint),
-                            );
-                        }
-                        SocketError { endpoint, reason } => {
-                            self.add_network_event(
-                                EventLevel::Error,
-                                format!("Socket error: {:?} (endpoint: {})", reason, endpoint),
-                            );
-                        }
-                    },
-                };
+                            endpoint,
+                            safe_id_display(&token)
+                        ),
+                    )
+                    .with_endpoint(endpoint)
+                    .with_token(token),
+                );
+            }
+            RecordedEvent::ReceiveFailed { endpoint, reason } => {
+                self.push_network_event(
+                    DisplayEvent::new(
+                        EventLevel::Error,
+                        format!("Receive failed: {} (endpoint: {})", reason, endpoint),
+                    )
+                    .with_endpoint(endpoint),
+                );
             }
-            ChatAppEvent::Info(info) => {
+            RecordedEvent::SocketError { endpoint, reason } => {
+                self.push_network_event(
+                    DisplayEvent::new(
+                        EventLevel::Error,
+                        format!("Socket error: {} (endpoint: {})", reason, endpoint),
+                    )
+                    .with_endpoint(endpoint),
+                );
+            }
+            RecordedEvent::Info(info) => {
                 self.add_app_event(EventLevel::Info, format!("Internal: {}", info))
             }
         }
@@ -318,6 +639,22 @@ impl DTChatApp {
             context_initialized: false,
         }
     }
+
+    /// Shows a recording of `recorded_peer`, see `MainView::replay`
+    pub fn replay(
+        chat_model: Arc<Mutex<ChatModel>>,
+        event_handler: Arc<Mutex<EventHandler>>,
+        recorded_peer: Option<&str>,
+    ) -> Result<Self, String> {
+        let ui = MainView::replay(chat_model.clone(), recorded_peer)?;
+
+        Ok(Self {
+            event_handler,
+            chat_model,
+            ui,
+            context_initialized: false,
+        })
+    }
 }
 
 impl App for DTChatApp {
@@ -329,23 +666,16 @@ impl App for DTChatApp {
             self.context_initialized = true;
         }
 
-        let mut update_request_with_events: Option<(
-            VecDeque<DisplayEvent>,
-            VecDeque<DisplayEvent>,
-        )> = None;
+        let mut update_request_with_events: Option<HandlerUpdate> = None;
         // Update the mirror of the model if something changed
         if let Ok(mut handler) = self.event_handler.lock() {
             if handler.refresh_model_request {
                 handler.refresh_model_request = false;
-                update_request_with_events = Some((
-                    handler.consume_app_events(),
-                    handler.consume_network_events(),
-                ));
+                update_request_with_events = Some(handler.consume_update());
             }
         }
-        if let Some((app_events, net_events)) = update_request_with_events {
-            self.ui
-                .will_lock_model_to_refresh(&self.chat_model, app_events, net_events);
+        if let Some(update) = update_request_with_events {
+            self.ui.will_lock_model_to_refresh(&self.chat_model, update);
         }
 
         CentralPanel::default().show(ctx, |ui| {
@@ -354,4 +684,9 @@ impl App for DTChatApp {
 
         ctx.request_repaint();
     }
+
+    /// The last changes would be lost until the next periodic save
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.ui.close_ui_state();
+    }
 }
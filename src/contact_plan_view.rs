@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use dtchat_backend::{dtchat::Peer, time::DTChatTime};
+use eframe::egui::{self, Color32, ScrollArea};
+use egui_plot::{uniform_grid_spacer, AxisHints, Bar, BarChart, GridMark, Legend, Plot, VLine};
+
+use crate::utils::contact_plan::{node_name, node_names, ContactPlan};
+
+pub struct ContactPlanView {
+    cp_path: Option<String>,
+    // relative times in the plan (+N) are anchored to the application start
+    reference: DTChatTime,
+    last_error: Option<String>,
+}
+
+fn time_str(ms: i64) -> String {
+    match DTChatTime::from_timestamp_millis(ms) {
+        Some(time) => time.ts_to_str(true, true, None, &chrono::Local),
+        None => "???".to_string(),
+    }
+}
+
+fn rate_str(bytes_per_sec: f64) -> String {
+    if bytes_per_sec >= 1_000_000.0 {
+        format!("{:.1} MB/s", bytes_per_sec / 1_000_000.0)
+    } else if bytes_per_sec >= 1_000.0 {
+        format!("{:.1} kB/s", bytes_per_sec / 1_000.0)
+    } else {
+        format!("{:.0} B/s", bytes_per_sec)
+    }
+}
+
+impl ContactPlanView {
+    pub fn new(cp_path: Option<String>) -> Self {
+        Self {
+            cp_path,
+            reference: DTChatTime::now(),
+            last_error: None,
+        }
+    }
+
+    pub fn load(&mut self) -> Option<ContactPlan> {
+        let Some(path) = &self.cp_path else {
+            self.last_error = Some("No contact plan configured (cp_path / CP_PATH)".to_string());
+            return None;
+        };
+        match ContactPlan::load(path, &self.reference) {
+            Ok(plan) => {
+                self.last_error = None;
+                Some(plan)
+            }
+            Err(err) => {
+                self.last_error = Some(err);
+                None
+            }
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        contact_plan: &mut Option<ContactPlan>,
+        local_peer: &Peer,
+        other_peers: &HashMap<String, Peer>,
+        current_time: &DTChatTime,
+    ) {
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            if ui.button("⟳ Reload").clicked() {
+                *contact_plan = self.load();
+            }
+            ui.separator();
+            match (&contact_plan, &self.last_error) {
+                (_, Some(err)) => {
+                    ui.colored_label(Color32::RED, err);
+                }
+                (Some(plan), None) => {
+                    ui.label(format!(
+                        "{}: {} contacts, {} ranges",
+                        plan.path,
+                        plan.contacts.len(),
+                        plan.ranges.len()
+                    ));
+                }
+                (None, None) => {
+                    ui.colored_label(Color32::GRAY, "No contact plan loaded");
+                }
+            }
+        });
+        ui.add_space(4.0);
+
+        let Some(plan) = contact_plan else {
+            return;
+        };
+        let names = node_names(local_peer, other_peers);
+        let pair_name =
+            |from: u64, to: u64| format!("{} → {}", node_name(&names, from), node_name(&names, to));
+
+        let now = current_time.timestamp_millis();
+        let available_height = ui.available_height();
+
+        ui.push_id("contact_plan_gantt", |ui| {
+            self.show_gantt(ui, plan, &pair_name, now, available_height * 0.5);
+        });
+        ui.separator();
+
+        ScrollArea::vertical()
+            .id_salt("contact_plan_tables")
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                ui.columns(2, |columns| {
+                    columns[0].heading("Contacts");
+                    egui::Grid::new("contacts_grid")
+                        .striped(true)
+                        .num_columns(4)
+                        .show(&mut columns[0], |ui| {
+                            ui.strong("Nodes");
+                            ui.strong("Start");
+                            ui.strong("End");
+                            ui.strong("Data rate");
+                            ui.end_row();
+                            for contact in &plan.contacts {
+                                ui.label(pair_name(contact.from, contact.to));
+                                ui.label(time_str(contact.start));
+                                ui.label(time_str(contact.end));
+                                ui.label(rate_str(contact.rate));
+                                ui.end_row();
+                            }
+                        });
+
+                    columns[1].heading("Ranges");
+                    egui::Grid::new("ranges_grid")
+                        .striped(true)
+                        .num_columns(4)
+                        .show(&mut columns[1], |ui| {
+                            ui.strong("Nodes");
+                            ui.strong("Start");
+                            ui.strong("End");
+                            ui.strong("One-way light time");
+                            ui.end_row();
+                            for range in &plan.ranges {
+                                ui.label(pair_name(range.from, range.to));
+                                ui.label(time_str(range.start));
+                                ui.label(time_str(range.end));
+                                ui.label(format!("{} s", range.owlt));
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+    }
+
+    fn show_gantt(
+        &self,
+        ui: &mut egui::Ui,
+        plan: &ContactPlan,
+        pair_name: &dyn Fn(u64, u64) -> String,
+        now: i64,
+        height: f32,
+    ) {
+        // one row per node pair
+        let mut pairs: Vec<(u64, u64)> = plan.contacts.iter().map(|c| (c.from, c.to)).collect();
+        pairs.sort();
+        pairs.dedup();
+        let row_names: Vec<String> = pairs.iter().map(|(f, t)| pair_name(*f, *t)).collect();
+
+        let mut active = Vec::new();
+        let mut scheduled = Vec::new();
+        for contact in &plan.contacts {
+            let Some(row) = pairs.iter().position(|p| *p == (contact.from, contact.to)) else {
+                continue;
+            };
+            let bar = Bar::new(row as f64, (contact.end - contact.start) as f64)
+                .base_offset(contact.start as f64)
+                .name(&row_names[row])
+                .width(0.6);
+            if contact.start <= now && now < contact.end {
+                active.push(bar);
+            } else {
+                scheduled.push(bar);
+            }
+        }
+
+        let x_axes = vec![
+            AxisHints::new_x()
+                .formatter(|x: GridMark, _range: &RangeInclusive<f64>| {
+                    match DTChatTime::from_timestamp_millis(x.value as i64) {
+                        Some(time) => time.ts_to_str(true, false, None, &chrono::Local),
+                        None => String::new(),
+                    }
+                })
+                .placement(egui_plot::VPlacement::Top),
+            AxisHints::new_x()
+                .formatter(|x: GridMark, _range: &RangeInclusive<f64>| {
+                    match DTChatTime::from_timestamp_millis(x.value as i64) {
+                        Some(time) => time.ts_to_str(false, true, None, &chrono::Local),
+                        None => String::new(),
+                    }
+                })
+                .placement(egui_plot::VPlacement::Bottom),
+        ];
+        let y_axes = vec![AxisHints::new_y()
+            .formatter(move |y: GridMark, _range: &RangeInclusive<f64>| {
+                if y.value < 0.0 || y.value.fract() != 0.0 {
+                    return String::new();
+                }
+                row_names.get(y.value as usize).cloned().unwrap_or_default()
+            })
+            .min_thickness(120.0)];
+
+        let formatter = |bar: &Bar, _chart: &BarChart| {
+            let start = bar.base_offset.unwrap_or_default();
+            let end = start + bar.value;
+            format!(
+                "{}\nfrom {}\nto {}",
+                bar.name,
+                time_str(start as i64),
+                time_str(end as i64)
+            )
+        };
+
+        Plot::new("Contact plan")
+            .allow_zoom(true)
+            .allow_drag(true)
+            .legend(Legend::default().position(egui_plot::Corner::RightTop))
+            .include_y(-0.5)
+            .include_y(pairs.len() as f64 - 0.5)
+            .custom_x_axes(x_axes)
+            .custom_y_axes(y_axes)
+            .y_grid_spacer(uniform_grid_spacer(|_| [1.0, 5.0, 10.0]))
+            .label_formatter(|_name, value| {
+                match DTChatTime::from_timestamp_millis(value.x as i64) {
+                    Some(time) => time.ts_to_str(false, true, None, &chrono::Local),
+                    None => String::new(),
+                }
+            })
+            .height(height.max(200.0))
+            .show(ui, |plot_ui| {
+                plot_ui.vline(
+                    VLine::new(now as f64)
+                        .color(Color32::ORANGE)
+                        .name("Current Time"),
+                );
+                plot_ui.bar_chart(
+                    BarChart::new(scheduled)
+                        .horizontal()
+                        .color(Color32::LIGHT_BLUE)
+                        .name("Scheduled contact")
+                        .element_formatter(Box::new(formatter)),
+                );
+                plot_ui.bar_chart(
+                    BarChart::new(active)
+                        .horizontal()
+                        .color(Color32::GREEN)
+                        .name("Active contact")
+                        .element_formatter(Box::new(formatter)),
+                );
+            });
+    }
+}
@@ -1,23 +1,41 @@
+use std::env;
 use std::error::Error;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+mod analytics_view;
 mod app;
+mod contact_plan_view;
 mod header_view;
 mod main_view;
 mod messages;
 mod network_view;
+mod toast_view;
 mod utils;
 
 use app::DTChatApp;
 use dtchat_backend::dtchat::ChatModel;
+use dtchat_backend::time::DTChatTime;
+use main_view::MirroredData;
+use messages::experiment::ExperimentLog;
 
 use dtchat_backend::Engine;
 use eframe::{App, NativeOptions};
+use utils::config::GuiConfig;
+use utils::recorder::{spawn_replay, EventRecorder, Recording};
+use utils::report::write_report;
+use utils::ui_state::UiState;
 
-fn main() -> Result<(), Box<dyn Error>> {
+/// Creates the model of the local peer (`PEER_UUID`) and connects it to a network engine.
+/// The events are recorded to `record_path`, if any.
+fn start_peer(record_path: Option<String>) -> Result<DTChatApp, String> {
     let event_handler = Arc::new(Mutex::new(app::EventHandler::new(100)));
 
     let model = ChatModel::new();
+    if let Some(path) = record_path {
+        let local_uuid = model.get_localpeer().uuid;
+        event_handler.lock().unwrap().recorder = Some(EventRecorder::create(&path, &local_uuid)?);
+    }
     let model_arc = Arc::new(Mutex::new(model));
 
     let app = DTChatApp::new(model_arc.clone(), event_handler.clone());
@@ -31,9 +49,94 @@ fn main() -> Result<(), Box<dyn Error>> {
     network_engine.add_observer(model_arc.clone());
 
     model_arc.lock().unwrap().start(network_engine);
+    Ok(app)
+}
+
+/// Offline: the recorded events replace the model and the network engine
+fn start_replay(path: &str) -> Result<DTChatApp, String> {
+    let recording = Recording::load(Path::new(path))?;
+    let speed = env::var("REPLAY_SPEED")
+        .ok()
+        .and_then(|speed| speed.parse::<f64>().ok())
+        .filter(|speed| *speed > 0.0)
+        .unwrap_or(1.0);
+
+    let event_handler = Arc::new(Mutex::new(app::EventHandler::new(100)));
+    event_handler.lock().unwrap().replayed_messages = Some(Vec::new());
+    if let Some(summary) = recording.skipped_summary() {
+        event_handler
+            .lock()
+            .unwrap()
+            .add_app_event(app::EventLevel::Error, summary);
+    }
+    let model_arc = Arc::new(Mutex::new(ChatModel::new()));
+    let app = DTChatApp::replay(
+        model_arc,
+        event_handler.clone(),
+        recording.local_peer_uuid.as_deref(),
+    )?;
+
+    spawn_replay(recording.lines, event_handler, speed);
+    Ok(app)
+}
+
+/// Without GUI: processes a whole recording at once and writes its HTML report
+fn write_recording_report(recording_path: &str, report_path: &str) -> Result<(), String> {
+    let recording = Recording::load(Path::new(recording_path))?;
+
+    let model_arc = Arc::new(Mutex::new(ChatModel::new()));
+    let local = model_arc.lock().unwrap().get_localpeer();
+    let mut data = MirroredData::new(local, &GuiConfig::load(), None);
+    // the report covers the whole recording
+    data.max_events = usize::MAX;
+    let mut event_handler = app::EventHandler::new(usize::MAX);
+    event_handler.replayed_messages = Some(Vec::new());
+    if let Some(summary) = recording.skipped_summary() {
+        elog_with_location!("{}", summary);
+        event_handler.add_app_event(app::EventLevel::Error, summary);
+    }
+
+    for line in recording.lines {
+        let timestamp = DTChatTime::from_timestamp_millis(line.wall_clock_ms);
+        event_handler.handle_event_at(line.event, timestamp);
+    }
+    data.refresh(&model_arc, event_handler.consume_update(), None);
+    // experiments saved by the GUI of the recorded peer, those with messages in the
+    // recording are reported
+    let mut experiments = ExperimentLog::new();
+    match &recording.local_peer_uuid {
+        Some(uuid) => {
+            data.swap_local_peer(&model_arc, uuid)?;
+            match UiState::load(&UiState::path(uuid)) {
+                Ok(state) => experiments.restore(&state.experiments),
+                Err(err) => elog_with_location!("{}", err),
+            }
+        }
+        None => elog_with_location!(
+            "{} has no header, its local peer is assumed to be {} and no experiment is reported",
+            recording_path,
+            data.local_peer.uuid
+        ),
+    }
+    write_report(Path::new(report_path), &data, &experiments)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    if let (Ok(recording), Ok(report_path)) = (env::var("REPLAY_PATH"), env::var("REPORT_PATH")) {
+        write_recording_report(&recording, &report_path)?;
+        println!("Report written to {}", report_path);
+        return Ok(());
+    }
+
+    let app = if let Ok(path) = env::var("REPLAY_PATH") {
+        start_replay(&path)?
+    } else {
+        start_peer(env::var("RECORD_PATH").ok())?
+    };
 
+    let window_size = app.ui.window_size.unwrap_or([900.0, 600.0]);
     let options = NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([900.0, 600.0]), // width, height
+        viewport: egui::ViewportBuilder::default().with_inner_size(window_size), // width, height
         ..Default::default()
     };
     eframe::run_native(
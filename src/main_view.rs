@@ -1,19 +1,61 @@
-use crate::app::DisplayEvent;
+use crate::analytics_view::AnalyticsView;
+use crate::app::{DisplayEvent, HandlerUpdate};
+use crate::contact_plan_view::ContactPlanView;
+use crate::elog_with_location;
 use crate::header_view::HeaderView;
 use crate::messages::MessagesView;
 use crate::network_view::NetworkView;
+use crate::toast_view::{pending_toasts, ToastView};
+use crate::utils::config::{GuiConfig, ParticipantConfig};
+use crate::utils::contact_plan::ContactPlan;
+use crate::utils::correlation::SocketLog;
+use crate::utils::history::MessageHistory;
+use crate::utils::journal::{EventCategory, EventJournal};
+use crate::utils::report::write_report;
+use crate::utils::ui_state::{UiState, UiStateWriter};
 use dtchat_backend::dtchat::{ChatModel, Peer, Room};
 use dtchat_backend::message::ChatMessage;
 use dtchat_backend::time::DTChatTime;
 use eframe::egui;
-use egui::{TopBottomPanel, Ui};
-use std::collections::{HashMap, VecDeque};
+use egui::{Color32, TopBottomPanel, Ui};
+use egui_file_dialog::FileDialog;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
 
+// older events are only kept in the journal
+const MAX_MIRRORED_EVENTS: usize = 1000;
+// the UI state is handed to its writer this often, which writes it when it changed
+const UI_STATE_SAVE_INTERVAL_MS: i64 = 2000;
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum ViewType {
     Messages,
     Network,
+    ContactPlan,
+    Analytics,
+}
+
+impl ViewType {
+    const ALL: [ViewType; 4] = [
+        ViewType::Messages,
+        ViewType::Network,
+        ViewType::ContactPlan,
+        ViewType::Analytics,
+    ];
+
+    /// Plain name, as saved in the UI state
+    pub fn saved_name(&self) -> &'static str {
+        match self {
+            ViewType::Messages => "messages",
+            ViewType::Network => "network",
+            ViewType::ContactPlan => "contact_plan",
+            ViewType::Analytics => "analytics",
+        }
+    }
+
+    pub fn from_saved_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|view| view.saved_name() == name)
+    }
 }
 
 pub struct MirroredData {
@@ -24,6 +66,141 @@ pub struct MirroredData {
     pub other_peers: HashMap<String, Peer>,
     pub rooms: HashMap<String, Room>,
     pub pbat_support_by_model: bool,
+    pub contact_plan: Option<ContactPlan>,
+    pub peer_colors: HashMap<String, Color32>,
+    // room uuid -> participants, from the configuration
+    pub room_participants: HashMap<String, Vec<ParticipantConfig>>,
+    pub history: MessageHistory,
+    pub socket_log: SocketLog,
+    // the messages come from a recording, nothing can be sent
+    pub replay: bool,
+    // replay mode only, message uuid -> index in the messages
+    replayed_index: HashMap<String, usize>,
+    // local peer of the model when the local peer is the one of a recording
+    model_local_peer: Option<Peer>,
+    // older events are dropped from the mirror
+    pub max_events: usize,
+    // number of events dropped from the mirror so far
+    pub omitted_events: usize,
+}
+
+impl MirroredData {
+    pub fn new(local_peer: Peer, config: &GuiConfig, contact_plan: Option<ContactPlan>) -> Self {
+        Self {
+            messages: vec![],
+            app_events: VecDeque::new(),
+            network_events: VecDeque::new(),
+            local_peer,
+            other_peers: HashMap::new(),
+            rooms: HashMap::new(),
+            pbat_support_by_model: false,
+            contact_plan,
+            peer_colors: config.peer_colors(),
+            room_participants: config.room_participants(),
+            history: MessageHistory::new(),
+            socket_log: SocketLog::new(),
+            replay: false,
+            replayed_index: HashMap::new(),
+            model_local_peer: None,
+            max_events: MAX_MIRRORED_EVENTS,
+            omitted_events: 0,
+        }
+    }
+
+    /// Applies the handler update and refreshes peers, rooms and messages from the model.
+    /// The new events are appended to the journal, if any. Returns the uuids of the
+    /// messages that failed since the previous refresh.
+    pub fn refresh(
+        &mut self,
+        chat_model: &Arc<Mutex<ChatModel>>,
+        update: HandlerUpdate,
+        mut journal: Option<&mut EventJournal>,
+    ) -> Vec<String> {
+        let update_time = update.latest_timestamp().unwrap_or_else(DTChatTime::now);
+        self.history.extend(update.message_transitions);
+        self.other_peers = self.other_peers_of(chat_model);
+        self.replay = update.replayed_messages.is_some();
+        match update.replayed_messages {
+            Some(changed) => self.merge_replayed(changed),
+            None => self.messages = chat_model.lock().unwrap().get_all_messages(),
+        }
+        let failed = self.history.record_failures(&self.messages, update_time);
+        self.pbat_support_by_model = chat_model.lock().unwrap().is_pbat_enabled();
+        self.rooms = chat_model.lock().unwrap().get_rooms();
+
+        // the socket events are linked to the message whose uuid is their token
+        let known: HashSet<&str> = self.messages.iter().map(|msg| msg.uuid.as_str()).collect();
+        self.socket_log.extend(update.socket_records, &known);
+        for (category, events) in [
+            (EventCategory::App, update.app_events),
+            (EventCategory::Network, update.network_events),
+        ] {
+            for mut event in events {
+                event.resolve(&self.local_peer, &self.other_peers);
+                if let Some(token) = event.token.as_deref().filter(|t| known.contains(t)) {
+                    event.message_uuid = Some(token.to_string());
+                }
+                if let Some(journal) = journal.as_deref_mut() {
+                    journal.append(category, &event);
+                }
+                match category {
+                    EventCategory::App => self.app_events.push_back(event),
+                    EventCategory::Network => self.network_events.push_back(event),
+                }
+            }
+        }
+        for events in [&mut self.app_events, &mut self.network_events] {
+            let overflow = events.len().saturating_sub(self.max_events);
+            events.drain(..overflow);
+            self.omitted_events += overflow;
+        }
+        failed
+    }
+
+    /// Makes the peer `uuid` the local peer, the local peer of the model becomes one of the
+    /// other peers, also after the next refreshes. The model takes its local peer from
+    /// `PEER_UUID`, a recording was made by another one.
+    pub fn swap_local_peer(
+        &mut self,
+        chat_model: &Arc<Mutex<ChatModel>>,
+        uuid: &str,
+    ) -> Result<(), String> {
+        if self.local_peer.uuid == uuid {
+            return Ok(());
+        }
+        let mut other_peers = self.other_peers_of(chat_model);
+        let peer = other_peers
+            .remove(uuid)
+            .ok_or_else(|| format!("Peer {} is not in the configuration", uuid))?;
+        let previous = std::mem::replace(&mut self.local_peer, peer);
+        self.model_local_peer = Some(previous);
+        self.other_peers = self.other_peers_of(chat_model);
+        Ok(())
+    }
+
+    /// Other peers of the model, seen from the local peer
+    fn other_peers_of(&self, chat_model: &Arc<Mutex<ChatModel>>) -> HashMap<String, Peer> {
+        let mut other_peers = chat_model.lock().unwrap().get_other_peers();
+        if let Some(model_local) = &self.model_local_peer {
+            other_peers.remove(&self.local_peer.uuid);
+            other_peers.insert(model_local.uuid.clone(), model_local.clone());
+        }
+        other_peers
+    }
+
+    /// Replaces the replayed messages that changed and appends the new ones
+    fn merge_replayed(&mut self, changed: Vec<ChatMessage>) {
+        for msg in changed {
+            match self.replayed_index.get(&msg.uuid) {
+                Some(idx) => self.messages[*idx] = msg,
+                None => {
+                    self.replayed_index
+                        .insert(msg.uuid.clone(), self.messages.len());
+                    self.messages.push(msg);
+                }
+            }
+        }
+    }
 }
 
 pub struct MainView {
@@ -31,52 +208,201 @@ pub struct MainView {
     pub header_view: HeaderView,
     pub message_view: MessagesView,
     pub network_view: NetworkView,
+    pub contact_plan_view: ContactPlanView,
+    pub analytics_view: AnalyticsView,
+    pub toast_view: ToastView,
 
     // current_view
     pub current_view: ViewType,
 
     // data
     pub data: MirroredData,
+    // None while replaying, the journal of the local peer is left alone
+    pub journal: Option<EventJournal>,
+
+    report_dialog: FileDialog,
+    last_report: Option<Result<String, String>>,
+
+    // preferences and layout, per local peer, None when the file must be left alone
+    ui_state_writer: Option<UiStateWriter>,
+    last_ui_state_check_ms: i64,
+    // inner size of the window, saved with the UI state
+    pub window_size: Option<[f32; 2]>,
 }
 
 impl MainView {
     pub fn new(local: Peer, model: Arc<Mutex<ChatModel>>) -> Self {
+        let journal = EventJournal::new(&local.uuid);
+        let ui_state_path = UiState::path(&local.uuid);
+        let (ui_state, ui_state_writer) = match UiState::load(&ui_state_path) {
+            Ok(ui_state) => (ui_state, Some(UiStateWriter::new(ui_state_path))),
+            Err(err) => {
+                elog_with_location!("{}, the UI state will not be saved", err);
+                (UiState::default(), None)
+            }
+        };
+        Self::with_state(local, model, Some(journal), ui_state, ui_state_writer)
+    }
+
+    /// Shows a recording made by `recorded_peer`, or by the local peer of the model when the
+    /// recording does not name it. Nothing is written to the journal or the UI state file
+    /// of the local peer, the views start with their defaults.
+    pub fn replay(
+        model: Arc<Mutex<ChatModel>>,
+        recorded_peer: Option<&str>,
+    ) -> Result<Self, String> {
+        let local = model.lock().unwrap().get_localpeer();
+        let mut view = Self::with_state(local, model.clone(), None, UiState::default(), None);
+        if let Some(uuid) = recorded_peer {
+            view.data.swap_local_peer(&model, uuid)?;
+        }
+        Ok(view)
+    }
+
+    fn with_state(
+        local: Peer,
+        model: Arc<Mutex<ChatModel>>,
+        journal: Option<EventJournal>,
+        ui_state: UiState,
+        ui_state_writer: Option<UiStateWriter>,
+    ) -> Self {
+        let config = GuiConfig::load();
+        let mut contact_plan_view = ContactPlanView::new(config.cp_path.clone());
+        let contact_plan = contact_plan_view.load();
+        let mut message_view = MessagesView::new(model);
+        message_view.restore_ui_state(&ui_state);
+        let mut toast_view = ToastView::new();
+        toast_view.do_not_disturb = ui_state.do_not_disturb;
+        let current_view = ui_state
+            .main_view
+            .as_deref()
+            .and_then(ViewType::from_saved_name)
+            .unwrap_or(ViewType::Messages);
+
         Self {
             header_view: HeaderView::new(),
-            message_view: MessagesView::new(model),
-            network_view: NetworkView {},
-            current_view: ViewType::Messages,
-            data: MirroredData {
-                messages: vec![],
-                app_events: VecDeque::new(),
-                network_events: VecDeque::new(),
-                local_peer: local,
-                other_peers: HashMap::new(),
-                rooms: HashMap::new(),
-                pbat_support_by_model: false,
-            },
+            message_view,
+            network_view: NetworkView::new(),
+            contact_plan_view,
+            analytics_view: AnalyticsView::new(),
+            toast_view,
+            current_view,
+            data: MirroredData::new(local, &config, contact_plan),
+            journal,
+            report_dialog: FileDialog::new(),
+            last_report: None,
+            ui_state_writer,
+            last_ui_state_check_ms: 0,
+            window_size: ui_state.window_size,
         }
     }
 
     pub fn will_lock_model_to_refresh(
         &mut self,
         chat_model: &Arc<Mutex<ChatModel>>,
-        app_events: VecDeque<DisplayEvent>,
-        network_events: VecDeque<DisplayEvent>,
+        update: HandlerUpdate,
     ) {
-        self.data.other_peers = chat_model.lock().unwrap().get_other_peers();
-        self.data.messages = chat_model.lock().unwrap().get_all_messages();
-        self.data.pbat_support_by_model = chat_model.lock().unwrap().is_pbat_enabled();
-        self.data.rooms = chat_model.lock().unwrap().get_rooms();
-
+        let pending = pending_toasts(&update);
+        let failed = self.data.refresh(chat_model, update, self.journal.as_mut());
+        self.message_view.fail_over(&failed, &self.data);
+        self.message_view.prune(&self.data);
         self.message_view.manage_message(&self.data);
 
-        self.data.app_events.extend(app_events);
-        self.data.network_events.extend(network_events);
+        // arrivals are not announced in the conversation being read
+        let viewed = self.message_view.read_conversation();
+        self.toast_view.notify(
+            pending,
+            &self.data,
+            viewed.as_deref(),
+            &self.message_view.room_selection_view.muted,
+        );
+    }
+
+    /// Writes the preferences and layout when they changed since the last check
+    fn persist_ui_state(&mut self, ctx: &egui::Context, current_time: &DTChatTime) {
+        if let Some(rect) = ctx.input(|i| i.viewport().inner_rect) {
+            self.window_size = Some([rect.width(), rect.height()]);
+        }
+        let now_ms = current_time.timestamp_millis();
+        if now_ms - self.last_ui_state_check_ms < UI_STATE_SAVE_INTERVAL_MS {
+            return;
+        }
+        self.last_ui_state_check_ms = now_ms;
+        self.save_ui_state();
+    }
+
+    /// Hands the preferences and layout to the writer, written if they changed since they
+    /// were last written
+    fn save_ui_state(&mut self) {
+        let Some(writer) = &self.ui_state_writer else {
+            return;
+        };
+        let mut state = UiState {
+            main_view: Some(self.current_view.saved_name().to_string()),
+            do_not_disturb: self.toast_view.do_not_disturb,
+            window_size: self.window_size,
+            ..Default::default()
+        };
+        self.message_view.store_ui_state(&mut state);
+        writer.save(state);
+    }
+
+    /// Saves the UI state a last time and waits for it to be written
+    pub fn close_ui_state(&mut self) {
+        self.save_ui_state();
+        if let Some(writer) = self.ui_state_writer.as_mut() {
+            writer.finish();
+        }
+    }
+
+    /// Saves the HTML report of the session, the result is shown next to the button
+    fn show_report_button(&mut self, ui: &mut Ui) {
+        self.report_dialog.update(ui.ctx());
+        if let Some(path) = self.report_dialog.take_picked() {
+            self.last_report = Some(
+                write_report(
+                    &path,
+                    &self.data,
+                    &self.message_view.message_prompt_view.experiments,
+                )
+                .map(|()| format!("Report written to {}", path.display())),
+            );
+        }
+
+        if ui
+            .button("\u{1F4C4} Report")
+            .on_hover_text("Save an HTML report of the session")
+            .clicked()
+        {
+            let file_name = format!(
+                "dtchat-report-{}-{}.html",
+                self.data.local_peer.name,
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            );
+            self.report_dialog = FileDialog::new().default_file_name(&file_name);
+            self.report_dialog.save_file();
+        }
+        match &self.last_report {
+            Some(Ok(text)) => {
+                ui.colored_label(Color32::GRAY, "\u{2714}")
+                    .on_hover_text(text);
+            }
+            Some(Err(err)) => {
+                ui.colored_label(Color32::RED, "\u{2716}")
+                    .on_hover_text(err);
+            }
+            None => (),
+        }
     }
 
     pub fn show(&mut self, ctx: &egui::Context, ui: &mut Ui) {
         let current_time = DTChatTime::now();
+        let focused = ctx.input(|i| i.focused);
+        self.message_view
+            .set_reading(self.current_view == ViewType::Messages && focused);
+        self.message_view
+            .message_prompt_view
+            .send_scheduled(&current_time);
 
         TopBottomPanel::top("header").show_inside(ui, |ui| {
             self.header_view
@@ -90,6 +416,16 @@ impl MainView {
                     "\u{2709} Messages",
                 );
                 ui.selectable_value(&mut self.current_view, ViewType::Network, "🖧 Network");
+                ui.selectable_value(
+                    &mut self.current_view,
+                    ViewType::ContactPlan,
+                    "🛰 Contact Plan",
+                );
+                ui.selectable_value(&mut self.current_view, ViewType::Analytics, "📊 Analytics");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    self.show_report_button(ui);
+                    self.toast_view.show_toggle(ui);
+                });
             });
             ui.add_space(3.0);
         });
@@ -100,9 +436,43 @@ impl MainView {
                     .show(ctx, &mut self.data, &current_time, ui);
             }
             ViewType::Network => {
-                self.network_view
-                    .show(ui, &self.data.network_events, &self.data.app_events);
+                if let Some(msg_uuid) = self.network_view.show(
+                    ui,
+                    &self.data.network_events,
+                    &self.data.app_events,
+                    &self.data.local_peer,
+                    &self.data.other_peers,
+                ) {
+                    self.current_view = ViewType::Messages;
+                    self.message_view.focus_message(msg_uuid);
+                }
+            }
+            ViewType::ContactPlan => {
+                self.contact_plan_view.show(
+                    ui,
+                    &mut self.data.contact_plan,
+                    &self.data.local_peer,
+                    &self.data.other_peers,
+                    &current_time,
+                );
+            }
+            ViewType::Analytics => {
+                self.analytics_view.show(
+                    ui,
+                    &self.data.messages,
+                    &self.data.local_peer,
+                    &self.data.other_peers,
+                    &self.data.rooms,
+                    &current_time,
+                );
+            }
+        }
+        if let Some(msg_uuid) = self.toast_view.show(ctx, &current_time) {
+            if let Some(msg) = self.data.messages.iter().find(|msg| msg.uuid == msg_uuid) {
+                self.current_view = ViewType::Messages;
+                self.message_view.show_in_conversation(msg, &self.data);
             }
         }
+        self.persist_ui_state(ctx, &current_time);
     }
 }
@@ -0,0 +1,214 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use dtchat_backend::dtchat::Peer;
+use dtchat_backend::message::ChatMessage;
+use dtchat_backend::Endpoint;
+use serde::{Deserialize, Serialize};
+
+use crate::messages::retry::RetryLinks;
+use crate::utils::font::{proto_from_name, proto_name, PrettyStr};
+
+// attempts of a message under the failover policy, the first one included
+pub const MAX_FAILOVER_ATTEMPTS: usize = 8;
+// latest direct messages whose endpoint is kept, the samples of best by history
+const MAX_SENT_RECORDS: usize = 1000;
+
+/// How the endpoint of a direct message is chosen. Rooms are out of scope: the backend
+/// picks the endpoint of each participant.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryPolicy {
+    // the endpoint selected in the composer
+    #[default]
+    Manual,
+    // the selected endpoint, then the next ones of the peer on failure
+    Failover,
+    // the endpoint with the lowest delay observed so far
+    BestByHistory,
+}
+
+impl DeliveryPolicy {
+    pub const ALL: [DeliveryPolicy; 3] = [
+        DeliveryPolicy::Manual,
+        DeliveryPolicy::Failover,
+        DeliveryPolicy::BestByHistory,
+    ];
+}
+
+impl PrettyStr for DeliveryPolicy {
+    fn to_pretty_str(&self) -> String {
+        match self {
+            DeliveryPolicy::Manual => "Manual".to_string(),
+            DeliveryPolicy::Failover => "Failover".to_string(),
+            DeliveryPolicy::BestByHistory => "Best by history".to_string(),
+        }
+    }
+}
+
+/// Endpoint a direct message was handed to, saved by protocol name
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SentEndpoint {
+    pub proto: String,
+    pub address: String,
+}
+
+impl SentEndpoint {
+    pub fn from(endpoint: &Endpoint) -> Self {
+        Self {
+            proto: proto_name(&endpoint.proto).to_string(),
+            address: endpoint.endpoint.clone(),
+        }
+    }
+
+    pub fn matches(&self, endpoint: &Endpoint) -> bool {
+        self.proto == proto_name(&endpoint.proto) && self.address == endpoint.endpoint
+    }
+}
+
+impl PrettyStr for SentEndpoint {
+    fn to_pretty_str(&self) -> String {
+        match proto_from_name(&self.proto) {
+            Some(proto) => format!("{} ({})", proto.to_pretty_str(), self.address),
+            None => format!("{} ({})", self.proto, self.address),
+        }
+    }
+}
+
+/// How a direct message was sent: its endpoint and the delivery policy of the peer then
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SentVia {
+    #[serde(flatten)]
+    pub endpoint: SentEndpoint,
+    #[serde(default)]
+    pub policy: DeliveryPolicy,
+}
+
+/// Endpoint chosen for each direct message sent from this window, recorded when the
+/// message is handed to the model. Saved with the UI state.
+#[derive(Default)]
+pub struct SentEndpoints {
+    // message uuid -> endpoint and policy
+    by_message: BTreeMap<String, SentVia>,
+}
+
+impl SentEndpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, msg_uuid: &str, endpoint: &Endpoint, policy: DeliveryPolicy) {
+        let sent = SentVia {
+            endpoint: SentEndpoint::from(endpoint),
+            policy,
+        };
+        self.by_message.insert(msg_uuid.to_string(), sent);
+    }
+
+    pub fn get(&self, msg_uuid: &str) -> Option<&SentVia> {
+        self.by_message.get(msg_uuid)
+    }
+
+    pub fn saved(&self) -> &BTreeMap<String, SentVia> {
+        &self.by_message
+    }
+
+    pub fn restore(&mut self, saved: &BTreeMap<String, SentVia>) {
+        self.by_message = saved.clone();
+    }
+
+    /// Forgets the messages no longer in the model, then the oldest ones beyond
+    /// MAX_SENT_RECORDS, in the order of the model
+    pub fn prune(&mut self, messages: &[ChatMessage]) {
+        let recorded: Vec<&str> = messages
+            .iter()
+            .map(|msg| msg.uuid.as_str())
+            .filter(|uuid| self.by_message.contains_key(*uuid))
+            .collect();
+        if recorded.len() == self.by_message.len() && recorded.len() <= MAX_SENT_RECORDS {
+            return;
+        }
+        let oldest = recorded.len().saturating_sub(MAX_SENT_RECORDS);
+        let kept: HashSet<&str> = recorded[oldest..].iter().copied().collect();
+        self.by_message
+            .retain(|uuid, _| kept.contains(uuid.as_str()));
+    }
+}
+
+/// Endpoint of the peer with the lowest median delay (ms) of the messages sent to it,
+/// None before any message sent through one of its endpoints was received
+pub fn best_endpoint(
+    peer: &Peer,
+    messages: &[ChatMessage],
+    local_peer: &Peer,
+    sent_via: &SentEndpoints,
+) -> Option<(Endpoint, i64)> {
+    let mut delays: HashMap<usize, Vec<i64>> = HashMap::new();
+    for msg in messages {
+        if msg.sender_uuid != local_peer.uuid || msg.room_uuid != peer.uuid {
+            continue;
+        }
+        let Some(received) = msg.receive_time else {
+            continue;
+        };
+        let Some(sent) = sent_via.get(&msg.uuid) else {
+            continue;
+        };
+        let Some(idx) = peer
+            .endpoints
+            .iter()
+            .position(|endpoint| sent.endpoint.matches(endpoint))
+        else {
+            continue;
+        };
+        delays
+            .entry(idx)
+            .or_default()
+            .push(received.timestamp_millis() - msg.send_time.timestamp_millis());
+    }
+    delays
+        .into_iter()
+        .map(|(idx, mut samples)| {
+            samples.sort_unstable();
+            (idx, samples[samples.len() / 2])
+        })
+        .min_by_key(|(idx, median)| (*median, *idx))
+        .map(|(idx, median)| (peer.endpoints[idx].clone(), median))
+}
+
+/// Endpoint of the peer following the one of the message, in the order of the
+/// configuration, skipping the endpoints already tried by the attempts it retries.
+/// None when the endpoint of the message was not recorded, or after
+/// MAX_FAILOVER_ATTEMPTS attempts of the original message.
+pub fn next_failover_endpoint(
+    peer: &Peer,
+    msg: &ChatMessage,
+    retries: &RetryLinks,
+    sent_via: &SentEndpoints,
+) -> Option<Endpoint> {
+    let last = &sent_via.get(&msg.uuid)?.endpoint;
+    let mut tried = vec![last];
+    let mut uuid = msg.uuid.as_str();
+    while let Some(original) = retries.original_of(uuid) {
+        if tried.len() >= MAX_FAILOVER_ATTEMPTS {
+            return None;
+        }
+        let Some(attempt) = sent_via.get(original) else {
+            break;
+        };
+        tried.push(&attempt.endpoint);
+        uuid = original;
+    }
+    if tried.len() >= MAX_FAILOVER_ATTEMPTS {
+        return None;
+    }
+    let count = peer.endpoints.len();
+    let start = peer
+        .endpoints
+        .iter()
+        .position(|endpoint| last.matches(endpoint))
+        .map_or(0, |idx| idx + 1);
+    (0..count)
+        .map(|offset| &peer.endpoints[(start + offset) % count])
+        .find(|endpoint| !tried.iter().any(|sent| sent.matches(endpoint)))
+        .cloned()
+}
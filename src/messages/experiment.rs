@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use dtchat_backend::dtchat::Peer;
+use dtchat_backend::message::{ChatMessage, Content};
+use dtchat_backend::time::DTChatTime;
+use dtchat_backend::Endpoint;
+use eframe::egui;
+use egui::{Color32, RichText, Ui};
+use serde::{Deserialize, Serialize};
+
+use crate::messages::delivery::SentEndpoint;
+use crate::utils::font::{pretty_duration, proto_from_name, PrettyStr, StatusDisplayHelper};
+
+// latest experiments kept, the older cards are dropped
+const MAX_EXPERIMENTS: usize = 100;
+
+fn time_str(time: Option<DTChatTime>) -> String {
+    match time {
+        Some(t) => t.ts_to_str(false, true, None, &chrono::Local),
+        None => "-".to_string(),
+    }
+}
+
+/// One copy of the payload, sent through one endpoint of the peer
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExperimentCopy {
+    pub endpoint: SentEndpoint,
+    // None when the model did not create a message
+    pub msg_uuid: Option<String>,
+}
+
+impl ExperimentCopy {
+    /// "UDP", the saved name when the protocol is unknown
+    pub fn proto_str(&self) -> String {
+        match proto_from_name(&self.endpoint.proto) {
+            Some(proto) => proto.to_pretty_str(),
+            None => self.endpoint.proto.clone(),
+        }
+    }
+}
+
+/// The same payload sent to one peer over each of its endpoints at once
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExperimentGroup {
+    pub id: u64,
+    pub peer_uuid: String,
+    pub peer_name: String,
+    // text, or path of the file sent
+    pub payload: String,
+    #[serde(default)]
+    pub is_file: bool,
+    pub sent_ms: i64,
+    pub copies: Vec<ExperimentCopy>,
+}
+
+impl ExperimentGroup {
+    /// Payload as shown in the cards, with a folder before a file path
+    pub fn payload_str(&self) -> String {
+        if self.is_file {
+            format!("\u{1F4C1} {}", self.payload)
+        } else {
+            self.payload.clone()
+        }
+    }
+
+    fn show(&self, ui: &mut Ui, messages: &[ChatMessage]) {
+        egui::Grid::new(("experiment_grid", self.id))
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                for header in [
+                    "Protocol",
+                    "Hand-off",
+                    "Arrival",
+                    "Delay",
+                    "Predicted",
+                    "Status",
+                ] {
+                    ui.label(RichText::new(header).color(Color32::GRAY));
+                }
+                ui.end_row();
+
+                for copy in &self.copies {
+                    ui.label(RichText::new(copy.proto_str()).strong())
+                        .on_hover_text(&copy.endpoint.address);
+                    let msg = copy
+                        .msg_uuid
+                        .as_ref()
+                        .and_then(|uuid| messages.iter().find(|msg| msg.uuid == *uuid));
+                    let Some(msg) = msg else {
+                        let reason = match copy.msg_uuid {
+                            Some(_) => "message not in the model",
+                            None => "not sent",
+                        };
+                        ui.colored_label(Color32::GRAY, reason);
+                        ui.end_row();
+                        continue;
+                    };
+                    let send_ms = msg.send_time.timestamp_millis();
+                    let after_send = |time: Option<DTChatTime>| match time {
+                        Some(t) => pretty_duration(t.timestamp_millis() - send_ms),
+                        None => "-".to_string(),
+                    };
+                    ui.label(after_send(msg.send_completed));
+                    ui.label(time_str(msg.receive_time));
+                    ui.label(after_send(msg.receive_time));
+                    let predicted = ui.label(time_str(msg.predicted_arrival_time));
+                    if let (Some(received), Some(pbat)) =
+                        (msg.receive_time, msg.predicted_arrival_time)
+                    {
+                        // positive: later than predicted
+                        predicted.on_hover_text(format!(
+                            "error {}",
+                            pretty_duration(received.timestamp_millis() - pbat.timestamp_millis())
+                        ));
+                    }
+                    ui.colored_label(msg.status.get_color(), msg.status.get_icon_text(""));
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// Experiments sent from this window, saved with the UI state
+#[derive(Default)]
+pub struct ExperimentLog {
+    groups: Vec<ExperimentGroup>,
+    next_id: u64,
+}
+
+impl ExperimentLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub fn groups(&self) -> &[ExperimentGroup] {
+        &self.groups
+    }
+
+    /// Replaces the experiments with the saved ones, the numbering goes on after them
+    pub fn restore(&mut self, groups: &[ExperimentGroup]) {
+        self.groups = groups.to_vec();
+        self.next_id = groups.iter().map(|group| group.id).max().unwrap_or(0);
+    }
+
+    /// Records an experiment, `sent` gives the message created for each endpoint of the
+    /// peer
+    pub fn start(
+        &mut self,
+        peer: &Peer,
+        content: &Content,
+        sent: Vec<(Endpoint, Option<String>)>,
+        now: &DTChatTime,
+    ) {
+        let (payload, is_file) = match content {
+            Content::Text(text) => (text.clone(), false),
+            Content::File(path) => (path.clone(), true),
+        };
+        self.next_id += 1;
+        self.groups.push(ExperimentGroup {
+            id: self.next_id,
+            peer_uuid: peer.uuid.clone(),
+            peer_name: peer.name.clone(),
+            payload,
+            is_file,
+            sent_ms: now.timestamp_millis(),
+            copies: sent
+                .into_iter()
+                .map(|(endpoint, msg_uuid)| ExperimentCopy {
+                    endpoint: SentEndpoint::from(&endpoint),
+                    msg_uuid,
+                })
+                .collect(),
+        });
+    }
+
+    /// Forgets the experiments without any copy left in the model, then the oldest ones
+    /// beyond MAX_EXPERIMENTS. The numbering goes on.
+    pub fn prune(&mut self, present: &HashSet<&str>) {
+        self.groups.retain(|group| {
+            group.copies.iter().any(|copy| {
+                copy.msg_uuid
+                    .as_deref()
+                    .is_some_and(|uuid| present.contains(uuid))
+            })
+        });
+        let oldest = self.groups.len().saturating_sub(MAX_EXPERIMENTS);
+        self.groups.drain(..oldest);
+    }
+
+    /// Message uuid -> id of its experiment
+    pub fn tags(&self) -> HashMap<String, u64> {
+        self.groups
+            .iter()
+            .flat_map(|group| {
+                group
+                    .copies
+                    .iter()
+                    .filter_map(|copy| Some((copy.msg_uuid.clone()?, group.id)))
+            })
+            .collect()
+    }
+
+    /// One comparison card per experiment, the latest one open
+    pub fn show(&mut self, ui: &mut Ui, messages: &[ChatMessage]) {
+        let mut cleared = false;
+        egui::CollapsingHeader::new(format!("\u{1F9EA} Experiments ({})", self.groups.len()))
+            .id_salt("experiments")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("experiments_scroll")
+                    .max_height(220.0)
+                    .show(ui, |ui| {
+                        let last = self.groups.len().saturating_sub(1);
+                        for (idx, group) in self.groups.iter().enumerate().rev() {
+                            let sent = DTChatTime::from_timestamp_millis(group.sent_ms);
+                            egui::CollapsingHeader::new(format!(
+                                "#{} to {} at {}: {}",
+                                group.id,
+                                group.peer_name,
+                                time_str(sent),
+                                group.payload_str()
+                            ))
+                            .id_salt(("experiment", group.id))
+                            .default_open(idx == last)
+                            .show(ui, |ui| group.show(ui, messages));
+                        }
+                    });
+                if ui.button("Clear").clicked() {
+                    cleared = true;
+                }
+            });
+        if cleared {
+            self.groups.clear();
+        }
+    }
+}
@@ -0,0 +1,635 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{Local, SecondsFormat, TimeZone};
+use dtchat_backend::dtchat::{Peer, Room};
+use dtchat_backend::message::{ChatMessage, MessageStatus};
+use dtchat_backend::time::DTChatTime;
+use eframe::egui;
+use egui::{Color32, Ui};
+use egui_file_dialog::FileDialog;
+use serde::Serialize;
+
+use crate::main_view::MirroredData;
+use crate::messages::experiment::ExperimentLog;
+use crate::utils::font::{pretty_duration, proto_name};
+use crate::utils::history::MessageHistory;
+use crate::utils::stats::Summary;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Markdown,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Markdown => "Markdown transcript",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+pub fn status_name(status: &MessageStatus) -> &'static str {
+    match status {
+        MessageStatus::Sending => "sending",
+        MessageStatus::Sent => "sent",
+        MessageStatus::Failed => "failed",
+        MessageStatus::Received => "received",
+        MessageStatus::ReceivedByPeer => "acked",
+    }
+}
+
+/// RFC 3339 in local time, with milliseconds
+fn iso_time(time: &DTChatTime) -> String {
+    match Local.timestamp_millis_opt(time.timestamp_millis()).single() {
+        Some(dt) => dt.to_rfc3339_opts(SecondsFormat::Millis, false),
+        None => String::new(),
+    }
+}
+
+fn opt_iso_time(time: &Option<DTChatTime>) -> String {
+    time.as_ref().map(iso_time).unwrap_or_default()
+}
+
+/// Names of the peers and rooms, the uuid when unknown
+struct Names<'a> {
+    local_peer: &'a Peer,
+    other_peers: &'a HashMap<String, Peer>,
+    rooms: &'a HashMap<String, Room>,
+}
+
+impl<'a> Names<'a> {
+    fn new(data: &'a MirroredData) -> Self {
+        Self {
+            local_peer: &data.local_peer,
+            other_peers: &data.other_peers,
+            rooms: &data.rooms,
+        }
+    }
+
+    fn peer(&self, uuid: &str) -> String {
+        if uuid == self.local_peer.uuid {
+            return self.local_peer.name.clone();
+        }
+        match self.other_peers.get(uuid) {
+            Some(peer) => peer.name.clone(),
+            None => uuid.to_string(),
+        }
+    }
+
+    /// Room name, or the peer name for a direct message
+    fn room(&self, uuid: &str) -> String {
+        match self.rooms.get(uuid) {
+            Some(room) => room.name.clone(),
+            None => self.peer(uuid),
+        }
+    }
+}
+
+/// One status transition of a message
+#[derive(Serialize)]
+struct ExportedTransition {
+    status: &'static str,
+    time: String,
+}
+
+/// One message with resolved names, as written to CSV and JSON
+#[derive(Serialize)]
+struct ExportedMessage {
+    uuid: String,
+    room: String,
+    room_uuid: String,
+    sender: String,
+    sender_uuid: String,
+    protocol: String,
+    source_endpoint: String,
+    status: String,
+    send_time: String,
+    send_completed: String,
+    receive_time: String,
+    predicted_arrival_time: String,
+    one_way_delay_ms: Option<i64>,
+    content: String,
+    history: Vec<ExportedTransition>,
+    // id of the experiment the message is a copy of
+    experiment: Option<u64>,
+}
+
+impl ExportedMessage {
+    fn new(
+        msg: &ChatMessage,
+        names: &Names,
+        history: &MessageHistory,
+        experiment_tags: &HashMap<String, u64>,
+    ) -> Self {
+        Self {
+            uuid: msg.uuid.clone(),
+            room: names.room(&msg.room_uuid),
+            room_uuid: msg.room_uuid.clone(),
+            sender: names.peer(&msg.sender_uuid),
+            sender_uuid: msg.sender_uuid.clone(),
+            protocol: proto_name(&msg.source_endpoint.proto).to_string(),
+            source_endpoint: msg.source_endpoint.endpoint.clone(),
+            status: status_name(&msg.status).to_string(),
+            send_time: iso_time(&msg.send_time),
+            send_completed: opt_iso_time(&msg.send_completed),
+            receive_time: opt_iso_time(&msg.receive_time),
+            predicted_arrival_time: opt_iso_time(&msg.predicted_arrival_time),
+            one_way_delay_ms: one_way_delay_ms(msg),
+            content: msg.content_as_string(),
+            history: history
+                .get(&msg.uuid)
+                .iter()
+                .map(|t| ExportedTransition {
+                    status: t.kind.name(),
+                    time: iso_time(&t.timestamp),
+                })
+                .collect(),
+            experiment: experiment_tags.get(&msg.uuid).copied(),
+        }
+    }
+}
+
+/// One copy of an experiment payload, with its message when the model created one
+#[derive(Serialize)]
+struct ExportedCopy {
+    protocol: String,
+    endpoint: String,
+    uuid: Option<String>,
+    status: Option<&'static str>,
+    one_way_delay_ms: Option<i64>,
+}
+
+/// The same payload sent to a peer over each of its endpoints
+#[derive(Serialize)]
+struct ExportedExperiment {
+    id: u64,
+    peer: String,
+    peer_uuid: String,
+    content: String,
+    is_file: bool,
+    sent_at: String,
+    copies: Vec<ExportedCopy>,
+}
+
+/// Experiments with at least one copy among the exported messages
+fn exported_experiments(
+    messages: &[ChatMessage],
+    experiments: &ExperimentLog,
+) -> Vec<ExportedExperiment> {
+    let find = |uuid: &Option<String>| {
+        let uuid = uuid.as_ref()?;
+        messages.iter().find(|msg| msg.uuid == *uuid)
+    };
+    experiments
+        .groups()
+        .iter()
+        .filter(|group| {
+            group
+                .copies
+                .iter()
+                .any(|copy| find(&copy.msg_uuid).is_some())
+        })
+        .map(|group| ExportedExperiment {
+            id: group.id,
+            peer: group.peer_name.clone(),
+            peer_uuid: group.peer_uuid.clone(),
+            content: group.payload.clone(),
+            is_file: group.is_file,
+            sent_at: DTChatTime::from_timestamp_millis(group.sent_ms)
+                .map(|time| iso_time(&time))
+                .unwrap_or_default(),
+            copies: group
+                .copies
+                .iter()
+                .map(|copy| {
+                    let msg = find(&copy.msg_uuid);
+                    ExportedCopy {
+                        protocol: copy.endpoint.proto.clone(),
+                        endpoint: copy.endpoint.address.clone(),
+                        uuid: copy.msg_uuid.clone(),
+                        status: msg.map(|msg| status_name(&msg.status)),
+                        one_way_delay_ms: msg.and_then(one_way_delay_ms),
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn one_way_delay_ms(msg: &ChatMessage) -> Option<i64> {
+    let received = msg.receive_time?.timestamp_millis();
+    Some(received - msg.send_time.timestamp_millis())
+}
+
+/// Counts by status and one-way delay of the exported messages
+#[derive(Serialize)]
+struct ExportedSummary {
+    messages: usize,
+    by_status: HashMap<&'static str, usize>,
+    delay_mean_ms: Option<f64>,
+    delay_p50_ms: Option<f64>,
+    delay_p90_ms: Option<f64>,
+    delay_max_ms: Option<f64>,
+}
+
+impl ExportedSummary {
+    fn of(messages: &[ChatMessage]) -> Self {
+        let mut by_status = HashMap::new();
+        for msg in messages {
+            *by_status.entry(status_name(&msg.status)).or_insert(0) += 1;
+        }
+        let delays: Vec<f64> = messages
+            .iter()
+            .filter_map(one_way_delay_ms)
+            .map(|ms| ms as f64)
+            .collect();
+        let delays = Summary::of(&delays);
+        Self {
+            messages: messages.len(),
+            by_status,
+            delay_mean_ms: delays.as_ref().map(|s| s.mean),
+            delay_p50_ms: delays.as_ref().map(|s| s.p50),
+            delay_p90_ms: delays.as_ref().map(|s| s.p90),
+            delay_max_ms: delays.as_ref().map(|s| s.max),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonExport {
+    exported_at: String,
+    local_peer: String,
+    summary: ExportedSummary,
+    messages: Vec<ExportedMessage>,
+    experiments: Vec<ExportedExperiment>,
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(
+    messages: &[ChatMessage],
+    names: &Names,
+    history: &MessageHistory,
+    experiments: &ExperimentLog,
+) -> String {
+    let mut out = String::from(
+        "uuid,room,room_uuid,sender,sender_uuid,protocol,source_endpoint,status,send_time,\
+         send_completed,receive_time,predicted_arrival_time,one_way_delay_ms,content,history,\
+         experiment\n",
+    );
+    let tags = experiments.tags();
+    for msg in messages {
+        out.push_str(&csv_row(ExportedMessage::new(msg, names, history, &tags)));
+    }
+    out
+}
+
+/// One line of the CSV export, newline included
+fn csv_row(m: ExportedMessage) -> String {
+    // "Sending 2025-01-01T12:00:01.000+01:00; Sent ..."
+    let transitions = m
+        .history
+        .iter()
+        .map(|t| format!("{} {}", t.status, t.time))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let row = [
+        m.uuid,
+        m.room,
+        m.room_uuid,
+        m.sender,
+        m.sender_uuid,
+        m.protocol,
+        m.source_endpoint,
+        m.status,
+        m.send_time,
+        m.send_completed,
+        m.receive_time,
+        m.predicted_arrival_time,
+        m.one_way_delay_ms
+            .map(|ms| ms.to_string())
+            .unwrap_or_default(),
+        m.content,
+        transitions,
+        m.experiment.map(|id| id.to_string()).unwrap_or_default(),
+    ];
+    let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+    row.join(",") + "\n"
+}
+
+fn to_json(
+    messages: &[ChatMessage],
+    names: &Names,
+    history: &MessageHistory,
+    experiments: &ExperimentLog,
+) -> Result<String, String> {
+    let tags = experiments.tags();
+    let export = JsonExport {
+        exported_at: iso_time(&DTChatTime::now()),
+        local_peer: names.local_peer.name.clone(),
+        summary: ExportedSummary::of(messages),
+        messages: messages
+            .iter()
+            .map(|msg| ExportedMessage::new(msg, names, history, &tags))
+            .collect(),
+        experiments: exported_experiments(messages, experiments),
+    };
+    serde_json::to_string_pretty(&export).map_err(|err| err.to_string())
+}
+
+/// `text` on one line of the transcript, shown as written: the Markdown control characters
+/// are escaped
+fn markdown_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' | '\r' => out.push(' '),
+            '\\' | '`' | '*' | '_' | '|' | '[' | ']' | '<' | '>' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    if out.starts_with('#') {
+        out.insert(0, '\\');
+    }
+    out
+}
+
+/// Transcript grouped by day, followed by the delivery summary
+fn to_markdown(
+    messages: &[ChatMessage],
+    names: &Names,
+    history: &MessageHistory,
+    experiments: &ExperimentLog,
+) -> String {
+    let tags = experiments.tags();
+    let now = DTChatTime::now();
+    let mut out = format!(
+        "# DTChat transcript ({})\n\nExported by {} on {}, {} messages.\n",
+        markdown_text(&names.local_peer.name),
+        markdown_text(&names.local_peer.name),
+        now.ts_to_str(true, true, None, &Local),
+        messages.len()
+    );
+
+    let mut current_day = String::new();
+    for msg in messages {
+        let day = msg.send_time.ts_to_str(true, false, None, &Local);
+        if day != current_day {
+            out.push_str(&format!("\n## {}\n\n", day));
+            current_day = day;
+        }
+        let mut line = format!(
+            "- **{}** {} \u{2192} {} ({}, {}): {}",
+            msg.send_time.ts_to_str(false, true, None, &Local),
+            markdown_text(&names.peer(&msg.sender_uuid)),
+            markdown_text(&names.room(&msg.room_uuid)),
+            proto_name(&msg.source_endpoint.proto),
+            status_name(&msg.status),
+            markdown_text(&msg.content_as_string())
+        );
+        if let Some(delay) = one_way_delay_ms(msg) {
+            line.push_str(&format!(" _(delivered in {})_", pretty_duration(delay)));
+        }
+        if let Some(id) = tags.get(&msg.uuid) {
+            line.push_str(&format!(" _(experiment #{})_", id));
+        }
+        out.push_str(&line);
+        out.push('\n');
+        if let Some(transitions) = history.describe(&msg.uuid) {
+            out.push_str(&format!("  - {}\n", transitions));
+        }
+    }
+
+    let summary = ExportedSummary::of(messages);
+    out.push_str("\n## Summary\n\n| Status | Messages |\n|---|---|\n");
+    let mut by_status: Vec<_> = summary.by_status.iter().collect();
+    by_status.sort();
+    for (status, count) in by_status {
+        out.push_str(&format!("| {} | {} |\n", status, count));
+    }
+    if let (Some(mean), Some(p50), Some(p90), Some(max)) = (
+        summary.delay_mean_ms,
+        summary.delay_p50_ms,
+        summary.delay_p90_ms,
+        summary.delay_max_ms,
+    ) {
+        out.push_str(&format!(
+            "\nOne-way delay: mean {}, p50 {}, p90 {}, max {}\n",
+            pretty_duration(mean.round() as i64),
+            pretty_duration(p50.round() as i64),
+            pretty_duration(p90.round() as i64),
+            pretty_duration(max.round() as i64)
+        ));
+    }
+    out
+}
+
+pub fn export(
+    path: &Path,
+    format: ExportFormat,
+    messages: &[ChatMessage],
+    data: &MirroredData,
+    experiments: &ExperimentLog,
+) -> Result<(), String> {
+    let names = Names::new(data);
+    let text = match format {
+        ExportFormat::Csv => to_csv(messages, &names, &data.history, experiments),
+        ExportFormat::Json => to_json(messages, &names, &data.history, experiments)?,
+        ExportFormat::Markdown => to_markdown(messages, &names, &data.history, experiments),
+    };
+    fs::write(path, text).map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+}
+
+/// Export menu of the settings bar, writes the messages currently displayed
+pub struct MessageExportView {
+    file_dialog: FileDialog,
+    pending: Option<ExportFormat>,
+    last_result: Option<Result<String, String>>,
+}
+
+impl MessageExportView {
+    pub fn new() -> Self {
+        Self {
+            file_dialog: FileDialog::new(),
+            pending: None,
+            last_result: None,
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        messages: &[ChatMessage],
+        data: &MirroredData,
+        experiments: &ExperimentLog,
+    ) {
+        self.file_dialog.update(ui.ctx());
+        if let (Some(path), Some(format)) = (self.file_dialog.take_picked(), self.pending) {
+            self.pending = None;
+            self.last_result = Some(
+                export(&path, format, messages, data, experiments).map(|()| {
+                    format!("{} messages exported to {}", messages.len(), path.display())
+                }),
+            );
+        }
+
+        ui.add_enabled_ui(!messages.is_empty(), |ui| {
+            ui.menu_button("\u{1F4BE} Export", |ui| {
+                for format in ExportFormat::ALL {
+                    if ui.button(format.name()).clicked() {
+                        let file_name = format!(
+                            "dtchat-{}-{}.{}",
+                            data.local_peer.name,
+                            Local::now().format("%Y%m%d-%H%M%S"),
+                            format.extension()
+                        );
+                        self.file_dialog = FileDialog::new().default_file_name(&file_name);
+                        self.file_dialog.save_file();
+                        self.pending = Some(format);
+                        ui.close_menu();
+                    }
+                }
+            })
+            .response
+            .on_hover_text(format!("Export the {} displayed messages", messages.len()));
+        });
+
+        match &self.last_result {
+            Some(Ok(text)) => {
+                ui.colored_label(Color32::GRAY, "\u{2714}")
+                    .on_hover_text(text);
+            }
+            Some(Err(err)) => {
+                ui.colored_label(Color32::RED, "\u{2716}")
+                    .on_hover_text(err);
+            }
+            None => (),
+        }
+    }
+}
+
+impl Default for MessageExportView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exported(content: &str) -> ExportedMessage {
+        ExportedMessage {
+            uuid: "m1".to_string(),
+            room: "Mars mission".to_string(),
+            room_uuid: "mars".to_string(),
+            sender: "Instance 1".to_string(),
+            sender_uuid: "1".to_string(),
+            protocol: "tcp".to_string(),
+            source_endpoint: "127.0.0.1:6500".to_string(),
+            status: "acked".to_string(),
+            send_time: "2025-01-01T12:00:00.000+00:00".to_string(),
+            send_completed: String::new(),
+            receive_time: String::new(),
+            predicted_arrival_time: String::new(),
+            one_way_delay_ms: Some(1500),
+            content: content.to_string(),
+            history: vec![
+                ExportedTransition {
+                    status: "Sending",
+                    time: "t0".to_string(),
+                },
+                ExportedTransition {
+                    status: "Sent",
+                    time: "t1".to_string(),
+                },
+            ],
+            experiment: None,
+        }
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("cr\r"), "\"cr\r\"");
+    }
+
+    #[test]
+    fn csv_row_has_every_column() {
+        let row = csv_row(exported("hello, \"world\""));
+        assert!(row.ends_with('\n'));
+        assert!(row.contains(",\"hello, \"\"world\"\"\","));
+        assert!(row.contains(",Sending t0; Sent t1,"));
+        // the header has as many columns, the quoted content holds one more comma
+        assert_eq!(row.matches(',').count(), 15 + 1);
+    }
+
+    #[test]
+    fn json_export_shape() {
+        let export = JsonExport {
+            exported_at: "now".to_string(),
+            local_peer: "Instance 1".to_string(),
+            summary: ExportedSummary {
+                messages: 1,
+                by_status: HashMap::from([("acked", 1)]),
+                delay_mean_ms: Some(1500.0),
+                delay_p50_ms: Some(1500.0),
+                delay_p90_ms: Some(1500.0),
+                delay_max_ms: Some(1500.0),
+            },
+            messages: vec![exported("hello")],
+            experiments: Vec::new(),
+        };
+        let value = serde_json::to_value(&export).unwrap();
+        assert_eq!(value["local_peer"], "Instance 1");
+        assert_eq!(value["summary"]["by_status"]["acked"], 1);
+        let msg = &value["messages"][0];
+        assert_eq!(msg["uuid"], "m1");
+        assert_eq!(msg["content"], "hello");
+        assert_eq!(msg["one_way_delay_ms"], 1500);
+        assert!(msg["experiment"].is_null());
+        assert_eq!(msg["history"][1]["status"], "Sent");
+        assert!(value["experiments"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn markdown_control_characters_are_escaped() {
+        assert_eq!(markdown_text("| a | b |"), "\\| a \\| b \\|");
+        assert_eq!(markdown_text("*bold* _it_"), "\\*bold\\* \\_it\\_");
+        assert_eq!(markdown_text("# title"), "\\# title");
+        assert_eq!(markdown_text("not # a title"), "not # a title");
+        assert_eq!(markdown_text("two\nlines"), "two lines");
+    }
+}
@@ -1,21 +1,37 @@
 use dtchat_backend::dtchat::Peer;
 use dtchat_backend::message::{ChatMessage, MessageStatus};
 use dtchat_backend::time::DTChatTime;
-use egui::Color32;
-use egui_plot::{AxisHints, BoxElem, BoxPlot, BoxSpread, GridMark, Legend, Plot, VLine};
+use egui::{Color32, Stroke};
+use egui_plot::{
+    AxisHints, BoxElem, BoxPlot, BoxSpread, GridMark, Legend, Plot, PlotPoints, Polygon, VLine,
+};
 use std::collections::HashMap;
 use std::ops::RangeInclusive;
 
 use crate::messages::graph_helper_view::GraphHelperView;
+use crate::messages::retry::{show_retry_menu, RetryAction};
+use crate::utils::contact_plan::{peer_nodes, ContactPlan};
 use crate::utils::font::StatusDisplayHelper;
 
 #[derive(Clone)]
 pub struct MessageGraphView {
-    auto_bounds: bool,
+    pub auto_bounds: bool,
     hovered: bool,
     helper_view: GraphHelperView,
     show_help_window: bool,
+    pub show_contacts: bool,
+    // message under the pointer when the context menu was opened
+    context_message: Option<String>,
+    // chosen in the menu of a failed message, handled by the messages view
+    pub retry_action: Option<RetryAction>,
 }
+
+struct ContactBand {
+    name: String,
+    color: Color32,
+    windows: Vec<(f64, f64)>,
+}
+
 #[allow(dead_code)]
 trait AutoReset {
     fn auto_reset(self, auto: bool) -> Self;
@@ -37,6 +53,9 @@ impl MessageGraphView {
             hovered: false,
             helper_view: GraphHelperView::new(),
             show_help_window: false,
+            show_contacts: true,
+            context_message: None,
+            retry_action: None,
         }
     }
 
@@ -87,6 +106,7 @@ impl MessageGraphView {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn show(
         &mut self,
         ctx: &egui::Context,
@@ -95,6 +115,9 @@ impl MessageGraphView {
         local_peer: &Peer,
         other_peers: &HashMap<String, Peer>,
         current_time: &DTChatTime,
+        contact_plan: Option<&ContactPlan>,
+        peer_colors: &HashMap<String, Color32>,
+        selected: &mut Option<String>,
     ) {
         let make_time_formatter = |show_date: bool, show_time: bool| {
             move |x: GridMark, _range: &RangeInclusive<f64>| {
@@ -119,6 +142,10 @@ impl MessageGraphView {
         ui.horizontal(|ui| {
             ui.checkbox(&mut self.auto_bounds, "Auto bounds");
             ui.separator();
+            if contact_plan.is_some() {
+                ui.checkbox(&mut self.show_contacts, "Contact windows");
+                ui.separator();
+            }
             let button_response = ui.add(egui::Button::new("\u{FF1F}"));
 
             if button_response.clicked() {
@@ -155,8 +182,10 @@ impl MessageGraphView {
         let mut last_message = now;
         // Group messages by sender and status (status converted to index)
         let mut grouped_boxes: HashMap<(String, MessageStatus), Vec<BoxElem>> = HashMap::new();
+        let mut spans = Vec::with_capacity(messages.len());
         for (index, message) in messages.iter().enumerate() {
             let (box_elem, from, to) = self.create_box_element(message, index as f64, now);
+            spans.push((from, to));
             if from < first_message {
                 first_message = from;
             }
@@ -177,6 +206,31 @@ impl MessageGraphView {
         };
         let plot_height = ui.available_height().max(300.0);
 
+        // Contact windows between the local node and each peer, clipped to the
+        // displayed time span so they do not drive the auto bounds
+        let mut contact_bands: Vec<ContactBand> = Vec::new();
+        if let (true, Some(plan)) = (self.show_contacts, contact_plan) {
+            let local_nodes = peer_nodes(local_peer);
+            let span_start = first_message;
+            let span_end = last_message + (last_message - first_message) * 0.2;
+            for peer in peers.values() {
+                let windows: Vec<(f64, f64)> = plan
+                    .contact_windows(&local_nodes, &peer_nodes(peer))
+                    .into_iter()
+                    .map(|(start, end)| (span_start.max(start as f64), span_end.min(end as f64)))
+                    .filter(|(start, end)| start < end)
+                    .collect();
+                if !windows.is_empty() {
+                    let color = *peer_colors.get(&peer.uuid).unwrap_or(&Color32::GRAY);
+                    contact_bands.push(ContactBand {
+                        name: format!("Contact with {}", peer.name),
+                        color,
+                        windows,
+                    });
+                }
+            }
+        }
+
         let plt = Plot::new("DTChat Timeline")
             .allow_zoom(true)
             .allow_drag(true)
@@ -189,11 +243,12 @@ impl MessageGraphView {
             .custom_x_axes(x_axes)
             .custom_y_axes(vec![])
             .label_formatter(|name, value| {
+                let time = DTChatTime::from_timestamp_millis(value.x as i64).unwrap();
+                let time = time.ts_to_str(false, true, None, &chrono::Local);
                 if !name.is_empty() {
-                    format!("{}: {:.*}%", name, 1, value.y)
+                    format!("{}\n{}", name, time)
                 } else {
-                    let value = DTChatTime::from_timestamp_millis(value.x as i64).unwrap();
-                    value.ts_to_str(false, true, None, &chrono::Local)
+                    time
                 }
             })
             .height(plot_height)
@@ -201,6 +256,24 @@ impl MessageGraphView {
                 plot_ui.set_auto_bounds(self.auto_bounds);
                 plot_ui.vline(VLine::new(now).color(Color32::ORANGE).name("Current Time"));
 
+                for band in &contact_bands {
+                    for (start, end) in &band.windows {
+                        let corners = vec![
+                            [*start, -0.5],
+                            [*end, -0.5],
+                            [*end, num_messages + 0.5],
+                            [*start, num_messages + 0.5],
+                        ];
+                        plot_ui.polygon(
+                            Polygon::new(PlotPoints::from(corners))
+                                .fill_color(band.color.gamma_multiply(0.15))
+                                .stroke(Stroke::NONE)
+                                .allow_hover(false)
+                                .name(&band.name),
+                        );
+                    }
+                }
+
                 for ((participant_uuid, status), boxes_for_peer_status) in grouped_boxes {
                     let participant_name = if local_peer.uuid == *participant_uuid {
                         "Me".to_string()
@@ -245,8 +318,41 @@ impl MessageGraphView {
 
                     plot_ui.box_plot(box_plot);
                 }
+
+                // index of the box under the pointer
+                let pointer = plot_ui.pointer_coordinate()?;
+                let index = pointer.y.round();
+                if index >= 0.0 && (index as usize) < spans.len() {
+                    let (from, to) = spans[index as usize];
+                    if from <= pointer.x && pointer.x <= to {
+                        return Some(index as usize);
+                    }
+                }
+                None
             });
         self.hovered = plt.response.hovered();
+        // A click on a box selects the message for the inspector
+        if let Some(index) = plt.inner {
+            if plt.response.clicked() {
+                *selected = Some(messages[index].uuid.clone());
+            }
+        }
+        if plt.response.secondary_clicked() {
+            self.context_message = plt.inner.map(|index| messages[index].uuid.clone());
+        }
+        let context_message = self
+            .context_message
+            .as_ref()
+            .and_then(|uuid| messages.iter().find(|msg| msg.uuid == *uuid));
+        if let Some(msg) = context_message {
+            if msg.status == MessageStatus::Failed && msg.sender_uuid == local_peer.uuid {
+                plt.response.context_menu(|ui| {
+                    if let Some(action) = show_retry_menu(ui, msg, local_peer, other_peers) {
+                        self.retry_action = Some(action);
+                    }
+                });
+            }
+        }
     }
 }
 
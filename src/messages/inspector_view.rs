@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use dtchat_backend::{
+    dtchat::{Peer, Room},
+    message::ChatMessage,
+    time::DTChatTime,
+};
+use eframe::egui;
+use egui::{Color32, RichText};
+
+use crate::messages::delivery::SentEndpoints;
+use crate::messages::retry::RetryLinks;
+use crate::utils::correlation::SocketLog;
+use crate::utils::font::{pretty_duration, PrettyStr, StatusDisplayHelper};
+use crate::utils::history::MessageHistory;
+use crate::utils::uuid::safe_id_display;
+
+pub struct MessageInspectorView {
+    // uuid of the message being inspected
+    pub selected: Option<String>,
+}
+
+fn time_str(time: &Option<DTChatTime>) -> String {
+    match time {
+        Some(t) => t.ts_to_str(true, true, None, &chrono::Local),
+        None => "-".to_string(),
+    }
+}
+
+fn peer_name(uuid: &str, local_peer: &Peer, other_peers: &HashMap<String, Peer>) -> String {
+    if local_peer.uuid == uuid {
+        format!("{} (me)", local_peer.name)
+    } else {
+        match other_peers.get(uuid) {
+            Some(peer) => peer.name.clone(),
+            None => format!("unknown ({})", uuid),
+        }
+    }
+}
+
+impl MessageInspectorView {
+    pub fn new() -> Self {
+        Self { selected: None }
+    }
+
+    fn field(ui: &mut egui::Ui, name: &str, value: impl Into<egui::WidgetText>) {
+        ui.label(RichText::new(name).color(Color32::GRAY));
+        ui.label(value);
+        ui.end_row();
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        messages: &[ChatMessage],
+        local_peer: &Peer,
+        other_peers: &HashMap<String, Peer>,
+        rooms: &HashMap<String, Room>,
+        history: &MessageHistory,
+        socket_log: &SocketLog,
+        retries: &RetryLinks,
+        sent_via: &SentEndpoints,
+    ) {
+        ui.horizontal(|ui| {
+            ui.heading("🔍 Inspector");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("\u{2716}").clicked() {
+                    self.selected = None;
+                }
+            });
+        });
+        ui.separator();
+
+        let Some(uuid) = &self.selected else {
+            return;
+        };
+        let Some(msg) = messages.iter().find(|m| m.uuid == *uuid) else {
+            ui.colored_label(Color32::GRAY, "Message not found");
+            return;
+        };
+
+        let room = match rooms.get(&msg.room_uuid) {
+            Some(room) => format!("\u{1F465} {}", room.name),
+            None => format!(
+                "\u{1F464} {}",
+                peer_name(&msg.room_uuid, local_peer, other_peers)
+            ),
+        };
+
+        let mut go_to = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("inspector_grid")
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    Self::field(ui, "uuid", &msg.uuid);
+                    if let Some(original) = retries.original_of(&msg.uuid) {
+                        ui.label(RichText::new("retry of").color(Color32::GRAY));
+                        if ui.link(safe_id_display(original)).clicked() {
+                            go_to = Some(original.to_string());
+                        }
+                        ui.end_row();
+                    }
+                    for retry in retries.retries_of(&msg.uuid) {
+                        ui.label(RichText::new("retried as").color(Color32::GRAY));
+                        if ui.link(safe_id_display(retry)).clicked() {
+                            go_to = Some(retry.to_string());
+                        }
+                        ui.end_row();
+                    }
+                    Self::field(ui, "room", room);
+                    Self::field(
+                        ui,
+                        "sender",
+                        peer_name(&msg.sender_uuid, local_peer, other_peers),
+                    );
+                    Self::field(ui, "source endpoint", msg.source_endpoint.to_pretty_str());
+                    // recorded for the direct messages sent from this window only
+                    if let Some(sent) = sent_via.get(&msg.uuid) {
+                        Self::field(ui, "sent via", sent.endpoint.to_pretty_str());
+                    }
+                    Self::field(
+                        ui,
+                        "status",
+                        RichText::new(msg.status.get_icon_text("")).color(msg.status.get_color()),
+                    );
+                    Self::field(ui, "send time", time_str(&Some(msg.send_time)));
+                    Self::field(ui, "send completed", time_str(&msg.send_completed));
+                    Self::field(ui, "receive time", time_str(&msg.receive_time));
+                    Self::field(
+                        ui,
+                        "predicted arrival",
+                        time_str(&msg.predicted_arrival_time),
+                    );
+
+                    let send_ms = msg.send_time.timestamp_millis();
+                    if let Some(completed) = msg.send_completed {
+                        Self::field(
+                            ui,
+                            "socket hand-off",
+                            pretty_duration(completed.timestamp_millis() - send_ms),
+                        );
+                    }
+                    if let Some(recv) = msg.receive_time {
+                        let recv_ms = recv.timestamp_millis();
+                        Self::field(ui, "one-way delay", pretty_duration(recv_ms - send_ms));
+                        if let Some(pred) = msg.predicted_arrival_time {
+                            // positive: later than predicted
+                            Self::field(
+                                ui,
+                                "prediction error",
+                                pretty_duration(recv_ms - pred.timestamp_millis()),
+                            );
+                        }
+                    }
+                });
+
+            ui.add_space(8.0);
+            ui.label(RichText::new("history").color(Color32::GRAY));
+            let transitions = history.get(&msg.uuid);
+            if transitions.is_empty() {
+                ui.label("No transition observed in this session");
+            }
+            for transition in transitions {
+                ui.label(transition.to_pretty_str());
+            }
+
+            ui.add_space(8.0);
+            ui.label(RichText::new("socket events").color(Color32::GRAY));
+            let records = socket_log.get(&msg.uuid);
+            if records.is_empty() {
+                ui.label("No socket event linked to this message");
+            }
+            for record in records {
+                if record.kind.is_error() {
+                    ui.colored_label(Color32::RED, record.to_pretty_str());
+                } else {
+                    ui.label(record.to_pretty_str());
+                }
+            }
+
+            ui.add_space(8.0);
+            ui.label(RichText::new("content").color(Color32::GRAY));
+            ui.label(msg.content_as_string());
+        });
+        if go_to.is_some() {
+            self.selected = go_to;
+        }
+    }
+}
+
+impl Default for MessageInspectorView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
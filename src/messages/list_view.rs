@@ -1,6 +1,15 @@
 use std::collections::HashMap;
 
-use crate::utils::{clock::Clock, font::StatusDisplayHelper};
+use crate::messages::delivery::SentEndpoints;
+use crate::messages::retry::{show_retry_menu, RetryAction, RetryLinks};
+use crate::messages::search_view::highlighted;
+use crate::messages::unread::arrival_ms;
+use crate::utils::{
+    clock::Clock,
+    font::{PrettyStr, StatusDisplayHelper},
+    history::MessageHistory,
+    uuid::safe_id_display,
+};
 use dtchat_backend::{
     dtchat::Peer,
     message::{ChatMessage, MessageStatus},
@@ -12,6 +21,16 @@ use egui::RichText;
 pub struct MessageListView {
     pub show_timestamps: bool,
     pub clock: Clock,
+    // search query whose matches are highlighted
+    pub highlight: Option<String>,
+    // message to bring into view, kept until it is displayed
+    pub scroll_to: Option<String>,
+    // messages received after this time are shown below a "New messages" divider
+    pub unread_divider_ms: Option<i64>,
+    // chosen in the menu of a failed message, handled by the messages view
+    pub retry_action: Option<RetryAction>,
+    // message uuid -> id of its experiment, set by the messages view
+    pub experiment_tags: HashMap<String, u64>,
 }
 
 impl MessageListView {
@@ -20,9 +39,25 @@ impl MessageListView {
         Self {
             show_timestamps: true,
             clock: Clock::new(&dumy_time, true),
+            highlight: None,
+            scroll_to: None,
+            unread_divider_ms: None,
+            retry_action: None,
+            experiment_tags: HashMap::new(),
+        }
+    }
+
+    /// Forgets the message to scroll to when the list does not show it, it would wait
+    /// for it forever
+    pub fn forget_unlisted_target(&mut self, messages: &[ChatMessage]) {
+        if let Some(uuid) = &self.scroll_to {
+            if !messages.iter().any(|msg| msg.uuid == *uuid) {
+                self.scroll_to = None;
+            }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn show(
         &mut self,
         ui: &mut egui::Ui,
@@ -30,8 +65,23 @@ impl MessageListView {
         current_time: &DTChatTime,
         local_peer: &Peer,
         other_peers: &HashMap<String, Peer>,
+        selected: &mut Option<String>,
+        history: &MessageHistory,
+        retries: &RetryLinks,
+        sent_via: &SentEndpoints,
     ) {
         self.clock.update(current_time);
+        let first_unread = self.unread_divider_ms.and_then(|divider| {
+            messages
+                .iter()
+                .find(|msg| msg.sender_uuid != local_peer.uuid && arrival_ms(msg) > divider)
+                .map(|msg| msg.uuid.clone())
+        });
+        if let Some(uuid) = &first_unread {
+            if ui.small_button("\u{2B07} Jump to first unread").clicked() {
+                self.scroll_to = Some(uuid.clone());
+            }
+        }
         egui::ScrollArea::vertical()
             .auto_shrink([false; 2])
             .stick_to_bottom(true)
@@ -40,7 +90,43 @@ impl MessageListView {
                     ui.colored_label(egui::Color32::GRAY, "No messages");
                 } else {
                     for message in messages.iter() {
-                        self.render(ui, message, local_peer, other_peers, self.clock.to_string());
+                        if first_unread.as_ref() == Some(&message.uuid) {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(egui::Color32::LIGHT_RED, "New messages");
+                                ui.add(egui::Separator::default().horizontal());
+                            });
+                        }
+                        let is_selected = selected.as_ref() == Some(&message.uuid);
+                        let response = self.render(
+                            ui,
+                            message,
+                            local_peer,
+                            other_peers,
+                            self.clock.to_string(),
+                            is_selected,
+                            history,
+                            retries,
+                            sent_via,
+                        );
+                        let response = response.interact(egui::Sense::click());
+                        if response.clicked() {
+                            *selected = Some(message.uuid.clone());
+                        }
+                        if message.status == MessageStatus::Failed
+                            && message.sender_uuid == local_peer.uuid
+                        {
+                            response.context_menu(|ui| {
+                                if let Some(action) =
+                                    show_retry_menu(ui, message, local_peer, other_peers)
+                                {
+                                    self.retry_action = Some(action);
+                                }
+                            });
+                        }
+                        if self.scroll_to.as_ref() == Some(&message.uuid) {
+                            response.scroll_to_me(Some(egui::Align::Center));
+                            self.scroll_to = None;
+                        }
                         ui.add_space(4.0);
                     }
                 }
@@ -48,6 +134,7 @@ impl MessageListView {
     }
 
     /// Rendre un message avec le format exact de dtchat_tui.rs
+    #[allow(clippy::too_many_arguments)]
     fn render(
         &self,
         ui: &mut egui::Ui,
@@ -55,8 +142,14 @@ impl MessageListView {
         local_peer: &Peer,
         other_peers: &HashMap<String, Peer>,
         clock_str: String,
-    ) {
-        ui.horizontal(|ui| {
+        is_selected: bool,
+        history: &MessageHistory,
+        retries: &RetryLinks,
+        sent_via: &SentEndpoints,
+    ) -> egui::Response {
+        // reserve the background so it is painted below the row content
+        let background = ui.painter().add(egui::Shape::Noop);
+        let row = ui.horizontal(|ui| {
             // Trouver le nom du peer expéditeur
 
             let peer = if local_peer.uuid == msg.sender_uuid {
@@ -67,7 +160,10 @@ impl MessageListView {
             let mut sep = "➡";
             // Status indicator avec couleurs selon le statut
 
-            ui.colored_label(msg.status.get_color(), msg.status.get_icon());
+            let status = ui.colored_label(msg.status.get_color(), msg.status.get_icon());
+            if let Some(transitions) = history.describe(&msg.uuid) {
+                status.on_hover_text(transitions);
+            }
             if self.show_timestamps {
                 // Format exact de dtchat_tui: [acked_time:send_time]
                 let receive_time_str = match msg.receive_time {
@@ -96,8 +192,47 @@ impl MessageListView {
                 ui.label(RichText::new(format!("{}:", peer_name)).strong());
             }
 
-            ui.label(msg.content_as_string());
+            match &self.highlight {
+                Some(query) => {
+                    let color = ui.visuals().text_color();
+                    ui.label(highlighted(ui, &msg.content_as_string(), query, color));
+                }
+                None => {
+                    ui.label(msg.content_as_string());
+                }
+            }
+
+            // endpoint that carried a direct message, it may differ from the one selected
+            if let Some(sent) = sent_via.get(&msg.uuid) {
+                ui.label(
+                    RichText::new(format!("via {}", sent.endpoint.to_pretty_str()))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+            }
+            if let Some(id) = self.experiment_tags.get(&msg.uuid) {
+                ui.colored_label(egui::Color32::GRAY, format!("\u{1F9EA} #{}", id))
+                    .on_hover_text(format!("Copy of experiment #{}", id));
+            }
+            if let Some(original) = retries.original_of(&msg.uuid) {
+                ui.colored_label(egui::Color32::GRAY, "\u{21BB}")
+                    .on_hover_text(format!("Retry of {}", safe_id_display(original)));
+            }
+            let retried = retries.retries_of(&msg.uuid).len();
+            if retried > 0 {
+                ui.colored_label(egui::Color32::GRAY, format!("\u{21BB} {}", retried))
+                    .on_hover_text(format!("Retried {} time(s)", retried));
+            }
         });
+
+        if is_selected {
+            let fill = ui.visuals().selection.bg_fill.gamma_multiply(0.4);
+            ui.painter().set(
+                background,
+                egui::Shape::rect_filled(row.response.rect, 2.0, fill),
+            );
+        }
+        row.response
     }
 }
 
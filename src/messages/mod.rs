@@ -1,32 +1,60 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{Arc, Mutex},
 };
 
 use dtchat_backend::{
     dtchat::{ChatModel, Peer, Room},
-    message::{sort_with_strategy, ChatMessage, SortStrategy},
+    message::{sort_with_strategy, ChatMessage, MessageStatus, SortStrategy},
     time::DTChatTime,
     Endpoint, EndpointProto,
 };
-use egui::{CentralPanel, TopBottomPanel, Ui};
+use egui::{CentralPanel, Color32, TopBottomPanel, Ui};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    elog_with_location,
     main_view::MirroredData,
+    messages::export::status_name,
+    messages::smart_view::{SavedMode, SavedSort, SmartView, SmartViewAction},
     messages::{
-        graph_view::MessageGraphView, list_view::MessageListView, prompt_view::MessagePromptView,
-        settings_view::MessageSettingsView, side_view::SideSelectionView,
+        delivery::{next_failover_endpoint, DeliveryPolicy},
+        export::MessageExportView,
+        graph_view::MessageGraphView,
+        inspector_view::MessageInspectorView,
+        list_view::MessageListView,
+        outbox::SendTarget,
+        prompt_view::MessagePromptView,
+        retry::RetryAction,
+        search_view::MessageSearchView,
+        sequence_view::MessageSequenceView,
+        settings_view::MessageSettingsView,
+        side_view::SideSelectionView,
+        unread::{conversation_uuid, UnreadTracker},
     },
-    utils::font::PrettyStr,
+    utils::font::{proto_from_name, proto_name, PrettyStr, StatusDisplayHelper},
+    utils::ui_state::{SavedConversation, UiState},
+    utils::uuid::safe_id_display,
 };
+pub mod delivery;
+pub mod experiment;
+pub mod export;
 pub mod graph_helper_view;
 pub mod graph_view;
+pub mod inspector_view;
 pub mod list_view;
+pub mod outbox;
 pub mod prompt_view;
+pub mod retry;
+pub mod search_view;
+pub mod sequence_view;
 pub mod settings_view;
 pub mod side_view;
+pub mod smart_view;
+pub mod unread;
 
-#[derive(PartialEq, Clone)]
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MessageCountToDisplay {
     Nothing,
     All,
@@ -38,7 +66,56 @@ struct Preferences {
     pub max_message_count: MessageCountToDisplay,
     pub sort_strategy: SortStrategy,
     pub protocol_filter: ProtoFilter,
+    pub status_filter: StatusFilter,
+    pub time_filter: TimeFilter,
     pub proto: Option<Endpoint>,
+    pub delivery_policy: DeliveryPolicy,
+}
+
+/// `Preferences` as saved in the UI state, the selected endpoint is not kept
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SavedPreferences {
+    max_message_count: MessageCountToDisplay,
+    sort: SavedSort,
+    #[serde(default)]
+    protocol: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default = "no_time_filter")]
+    time_filter: TimeFilter,
+    #[serde(default)]
+    delivery_policy: DeliveryPolicy,
+}
+
+fn no_time_filter() -> TimeFilter {
+    TimeFilter::NoFilter
+}
+
+impl From<&Preferences> for SavedPreferences {
+    fn from(prefs: &Preferences) -> Self {
+        Self {
+            max_message_count: prefs.max_message_count.clone(),
+            sort: SavedSort::from(&prefs.sort_strategy),
+            protocol: prefs.protocol_filter.saved_name(),
+            status: prefs.status_filter.saved_name(),
+            time_filter: prefs.time_filter,
+            delivery_policy: prefs.delivery_policy,
+        }
+    }
+}
+
+impl SavedPreferences {
+    fn restore(&self) -> Preferences {
+        Preferences {
+            max_message_count: self.max_message_count.clone(),
+            sort_strategy: self.sort.strategy(),
+            protocol_filter: ProtoFilter::from_saved_name(self.protocol.as_deref()),
+            status_filter: StatusFilter::from_saved_name(self.status.as_deref()),
+            time_filter: self.time_filter,
+            proto: None,
+            delivery_policy: self.delivery_policy,
+        }
+    }
 }
 
 impl Preferences {
@@ -47,7 +124,10 @@ impl Preferences {
             max_message_count: MessageCountToDisplay::All,
             sort_strategy: SortStrategy::Standard,
             protocol_filter: ProtoFilter::NoFilter,
+            status_filter: StatusFilter::NoFilter,
+            time_filter: TimeFilter::NoFilter,
             proto: None,
+            delivery_policy: DeliveryPolicy::Manual,
         }
     }
 }
@@ -56,6 +136,8 @@ pub struct PreferencesContext {
     last_uuid: Option<String>,
     current_context: Preferences,
     context_map: HashMap<String, Preferences>,
+    // stored preferences of the current context while a smart view is applied over them
+    overlaid_context: Option<Preferences>,
 }
 
 impl PreferencesContext {
@@ -64,10 +146,14 @@ impl PreferencesContext {
             last_uuid: None,
             current_context: Preferences::new(),
             context_map: HashMap::new(),
+            overlaid_context: None,
         }
     }
 
+    /// Switches to the stored preferences of `uuid`, ending any smart view applied over the
+    /// current ones
     pub fn load_context(&mut self, uuid: &str) {
+        self.end_overlay();
         if let Some(ref last_uuid) = self.last_uuid {
             if last_uuid != uuid {
                 self.context_map
@@ -83,19 +169,99 @@ impl PreferencesContext {
 
         self.last_uuid = Some(uuid.to_string());
     }
+
+    /// Applies `apply` to the current context without changing the stored preferences,
+    /// until the next `end_overlay` or `load_context`
+    fn overlay(&mut self, apply: impl FnOnce(&mut Preferences)) {
+        if self.overlaid_context.is_none() {
+            self.overlaid_context = Some(self.current_context.clone());
+        }
+        apply(&mut self.current_context);
+    }
+
+    /// Back to the stored preferences of the current context, false without overlay
+    pub fn end_overlay(&mut self) -> bool {
+        match self.overlaid_context.take() {
+            Some(stored) => {
+                self.current_context = stored;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn stored_context(&self) -> &Preferences {
+        self.overlaid_context
+            .as_ref()
+            .unwrap_or(&self.current_context)
+    }
+
+    pub fn delivery_policy(&self, uuid: &str) -> DeliveryPolicy {
+        if self.last_uuid.as_deref() == Some(uuid) {
+            return self.current_context.delivery_policy;
+        }
+        self.context_map
+            .get(uuid)
+            .map_or(DeliveryPolicy::Manual, |prefs| prefs.delivery_policy)
+    }
+
+    /// Preferences of every context, including the current one, and the current context
+    pub fn save(&self) -> (BTreeMap<String, SavedPreferences>, Option<String>) {
+        let mut saved: BTreeMap<String, SavedPreferences> = self
+            .context_map
+            .iter()
+            .map(|(uuid, prefs)| (uuid.clone(), SavedPreferences::from(prefs)))
+            .collect();
+        if let Some(uuid) = &self.last_uuid {
+            saved.insert(uuid.clone(), SavedPreferences::from(self.stored_context()));
+        }
+        (saved, self.last_uuid.clone())
+    }
+
+    pub fn restore(&mut self, saved: &BTreeMap<String, SavedPreferences>, current: Option<&str>) {
+        self.context_map = saved
+            .iter()
+            .map(|(uuid, prefs)| (uuid.clone(), prefs.restore()))
+            .collect();
+        self.last_uuid = None;
+        if let Some(uuid) = current {
+            self.load_context(uuid);
+        }
+    }
 }
 
 #[derive(PartialEq, Clone, Copy)]
 pub enum MessageViewType {
-    MessageGraph,
-    MessageList,
+    Graph,
+    List,
+    Sequence,
 }
 
 impl MessageViewType {
+    const ALL: [MessageViewType; 3] = [
+        MessageViewType::Graph,
+        MessageViewType::List,
+        MessageViewType::Sequence,
+    ];
+
+    /// Plain name, as saved in the UI state
+    pub fn saved_name(&self) -> &'static str {
+        match self {
+            MessageViewType::Graph => "graph",
+            MessageViewType::List => "list",
+            MessageViewType::Sequence => "sequence",
+        }
+    }
+
+    pub fn from_saved_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|view| view.saved_name() == name)
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
-            MessageViewType::MessageGraph => "📈 Graph",
-            MessageViewType::MessageList => "💬 List ",
+            MessageViewType::Graph => "📈 Graph",
+            MessageViewType::List => "💬 List ",
+            MessageViewType::Sequence => "🔀 Sequence",
         }
     }
 }
@@ -113,6 +279,23 @@ pub enum ProtoFilter {
     Filter(EndpointProto),
 }
 
+impl ProtoFilter {
+    /// Protocol name as in the configuration, None without filter
+    pub fn saved_name(&self) -> Option<String> {
+        match self {
+            ProtoFilter::NoFilter => None,
+            ProtoFilter::Filter(proto) => Some(proto_name(proto).to_string()),
+        }
+    }
+
+    pub fn from_saved_name(name: Option<&str>) -> Self {
+        match name.and_then(proto_from_name) {
+            Some(proto) => ProtoFilter::Filter(proto),
+            None => ProtoFilter::NoFilter,
+        }
+    }
+}
+
 impl PrettyStr for ProtoFilter {
     fn to_pretty_str(&self) -> String {
         match self {
@@ -122,6 +305,130 @@ impl PrettyStr for ProtoFilter {
     }
 }
 
+#[derive(Clone, PartialEq)]
+pub enum StatusFilter {
+    NoFilter,
+    Status(MessageStatus),
+    // sending or sent, not received yet
+    InFlight,
+    // in flight while the predicted arrival time is past
+    Overdue,
+}
+
+fn is_in_flight(msg: &ChatMessage) -> bool {
+    matches!(msg.status, MessageStatus::Sending | MessageStatus::Sent)
+}
+
+impl StatusFilter {
+    pub const ALL: [StatusFilter; 8] = [
+        StatusFilter::NoFilter,
+        StatusFilter::Status(MessageStatus::Sending),
+        StatusFilter::Status(MessageStatus::Sent),
+        StatusFilter::Status(MessageStatus::Failed),
+        StatusFilter::Status(MessageStatus::Received),
+        StatusFilter::Status(MessageStatus::ReceivedByPeer),
+        StatusFilter::InFlight,
+        StatusFilter::Overdue,
+    ];
+
+    /// Plain name, as saved in the UI state
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusFilter::NoFilter => "all",
+            StatusFilter::Status(status) => status_name(status),
+            StatusFilter::InFlight => "in_flight",
+            StatusFilter::Overdue => "overdue",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|filter| filter.name() == name)
+    }
+
+    /// None without filter
+    pub fn saved_name(&self) -> Option<String> {
+        match self {
+            StatusFilter::NoFilter => None,
+            filter => Some(filter.name().to_string()),
+        }
+    }
+
+    pub fn from_saved_name(name: Option<&str>) -> Self {
+        name.and_then(Self::from_name)
+            .unwrap_or(StatusFilter::NoFilter)
+    }
+
+    pub fn accepts(&self, msg: &ChatMessage, now: &DTChatTime) -> bool {
+        match self {
+            StatusFilter::NoFilter => true,
+            StatusFilter::Status(status) => msg.status == *status,
+            StatusFilter::InFlight => is_in_flight(msg),
+            StatusFilter::Overdue => {
+                is_in_flight(msg)
+                    && msg
+                        .predicted_arrival_time
+                        .is_some_and(|pbat| pbat.timestamp_millis() < now.timestamp_millis())
+            }
+        }
+    }
+}
+
+impl PrettyStr for StatusFilter {
+    fn to_pretty_str(&self) -> String {
+        match self {
+            StatusFilter::NoFilter => "All status".to_string(),
+            StatusFilter::Status(status) => {
+                format!("{} {}", status.get_icon(), status_name(status))
+            }
+            StatusFilter::InFlight => "In flight".to_string(),
+            StatusFilter::Overdue => "In flight past prediction".to_string(),
+        }
+    }
+}
+
+/// On the send time of the messages
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeFilter {
+    NoFilter,
+    LastMinutes(i64),
+    // timestamps in ms, both included
+    Between(i64, i64),
+}
+
+impl TimeFilter {
+    pub fn accepts(&self, msg: &ChatMessage, now: &DTChatTime) -> bool {
+        let sent = msg.send_time.timestamp_millis();
+        match self {
+            TimeFilter::NoFilter => true,
+            TimeFilter::LastMinutes(minutes) => sent >= now.timestamp_millis() - minutes * 60_000,
+            TimeFilter::Between(from, to) => (*from..=*to).contains(&sent),
+        }
+    }
+
+    /// The filtered messages change with the current time
+    pub fn is_relative(&self) -> bool {
+        matches!(self, TimeFilter::LastMinutes(_))
+    }
+}
+
+impl PrettyStr for TimeFilter {
+    fn to_pretty_str(&self) -> String {
+        let time_str = |ms: i64| match DTChatTime::from_timestamp_millis(ms) {
+            Some(time) => time.ts_to_str(true, true, None, &chrono::Local),
+            None => "???".to_string(),
+        };
+        match self {
+            TimeFilter::NoFilter => "All time".to_string(),
+            TimeFilter::LastMinutes(minutes) if minutes % 60 == 0 => {
+                format!("Last {} h", minutes / 60)
+            }
+            TimeFilter::LastMinutes(minutes) => format!("Last {} min", minutes),
+            TimeFilter::Between(from, to) => format!("{} - {}", time_str(*from), time_str(*to)),
+        }
+    }
+}
+
 impl std::fmt::Display for ProtoFilter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -143,6 +450,10 @@ pub struct MessagesView {
     pub message_settings_view: MessageSettingsView,
     pub message_list_view: MessageListView,
     pub message_graph_view: MessageGraphView,
+    pub message_sequence_view: MessageSequenceView,
+    pub message_inspector_view: MessageInspectorView,
+    message_export_view: MessageExportView,
+    message_search_view: MessageSearchView,
     pub room_selection_view: SideSelectionView,
 
     // view to display
@@ -150,6 +461,20 @@ pub struct MessagesView {
 
     // messages:
     pub messages_to_display: Vec<ChatMessage>,
+    unread: UnreadTracker,
+    // the conversation is on screen in a focused window, its messages are being read
+    reading: bool,
+    // failed messages already handled by the failover policy
+    failed_over: HashSet<String>,
+    // messages failing before are not failed over
+    started_ms: i64,
+    // restored once the peers and rooms are known
+    restored_conversation: Option<SavedConversation>,
+    side_panel_width: f32,
+    inspector_width: f32,
+    // time of the last filtering, filters depending on the current time are applied again
+    // every second
+    last_filtering_ms: i64,
 }
 
 impl MessagesView {
@@ -157,19 +482,150 @@ impl MessagesView {
         Self {
             message_prompt_view: MessagePromptView::new(model),
             message_settings_view: MessageSettingsView::new(),
-            current_view: MessageViewType::MessageGraph,
+            current_view: MessageViewType::Graph,
             request_filter: false,
             pref_ctx: PreferencesContext::new(),
 
             current_mode: MessagingMode::All,
             message_list_view: MessageListView::new(),
             message_graph_view: MessageGraphView::new(),
+            message_sequence_view: MessageSequenceView::new(),
+            message_inspector_view: MessageInspectorView::new(),
+            message_export_view: MessageExportView::new(),
+            message_search_view: MessageSearchView::new(),
             room_selection_view: SideSelectionView::new(),
             messages_to_display: Vec::new(),
+            last_filtering_ms: 0,
+            unread: UnreadTracker::new(DTChatTime::now().timestamp_millis()),
+            reading: false,
+            failed_over: HashSet::new(),
+            started_ms: DTChatTime::now().timestamp_millis(),
+            restored_conversation: None,
+            side_panel_width: 115.0,
+            inspector_width: 260.0,
+        }
+    }
+
+    /// Applies the state saved by a previous session
+    pub fn restore_ui_state(&mut self, state: &UiState) {
+        self.room_selection_view.smart_views = state.smart_views.clone();
+        if let Some(view) = state
+            .message_view
+            .as_deref()
+            .and_then(MessageViewType::from_saved_name)
+        {
+            self.current_view = view;
+        }
+        self.pref_ctx
+            .restore(&state.preferences, state.current_context.as_deref());
+        self.restored_conversation = state.conversation.clone();
+        self.unread.last_read = state.last_read.clone();
+        self.room_selection_view.muted = state.muted.clone();
+        if let Some(since_ms) = state.unread_since {
+            self.unread.since_ms = since_ms;
+        }
+        if let Some(width) = state.side_panel_width {
+            self.side_panel_width = width;
+        }
+        if let Some(width) = state.inspector_width {
+            self.inspector_width = width;
+        }
+        if let Some(auto_bounds) = state.graph_auto_bounds {
+            self.message_graph_view.auto_bounds = auto_bounds;
         }
+        if let Some(show_contacts) = state.graph_show_contacts {
+            self.message_graph_view.show_contacts = show_contacts;
+        }
+        if let Some(auto_bounds) = state.sequence_auto_bounds {
+            self.message_sequence_view.auto_bounds = auto_bounds;
+        }
+        if let Some(show_timestamps) = state.list_show_timestamps {
+            self.message_list_view.show_timestamps = show_timestamps;
+        }
+        self.message_prompt_view
+            .retry_links
+            .restore(&state.retry_links);
+        self.message_prompt_view.sent_via.restore(&state.sent_via);
+        self.message_prompt_view
+            .experiments
+            .restore(&state.experiments);
+        self.request_filter = true;
+    }
+
+    pub fn store_ui_state(&self, state: &mut UiState) {
+        state.smart_views = self.room_selection_view.smart_views.clone();
+        state.message_view = Some(self.current_view.saved_name().to_string());
+        (state.preferences, state.current_context) = self.pref_ctx.save();
+        // not restored yet, kept as is
+        state.conversation = match &self.restored_conversation {
+            Some(conversation) => Some(conversation.clone()),
+            None => {
+                let (last_peer, last_room) = self.room_selection_view.remembered();
+                Some(SavedConversation {
+                    mode: SavedMode::from(&self.current_mode),
+                    last_peer: last_peer.map(|peer| peer.uuid.clone()),
+                    last_room: last_room.map(|room| room.uuid.clone()),
+                })
+            }
+        };
+        state.last_read = self.unread.last_read.clone();
+        state.unread_since = Some(self.unread.since_ms);
+        state.muted = self.room_selection_view.muted.clone();
+        state.retry_links = self.message_prompt_view.retry_links.links().to_vec();
+        state.sent_via = self.message_prompt_view.sent_via.saved().clone();
+        state.experiments = self.message_prompt_view.experiments.groups().to_vec();
+        state.side_panel_width = Some(self.side_panel_width);
+        state.inspector_width = Some(self.inspector_width);
+        state.graph_auto_bounds = Some(self.message_graph_view.auto_bounds);
+        state.graph_show_contacts = Some(self.message_graph_view.show_contacts);
+        state.sequence_auto_bounds = Some(self.message_sequence_view.auto_bounds);
+        state.list_show_timestamps = Some(self.message_list_view.show_timestamps);
+    }
+
+    fn restore_conversation(&mut self, conversation: SavedConversation, data: &MirroredData) {
+        if let Some(peer) = conversation
+            .last_peer
+            .and_then(|uuid| data.other_peers.get(&uuid))
+        {
+            self.room_selection_view
+                .remember(&MessagingMode::Peer(Some(peer.clone())));
+        }
+        if let Some(room) = conversation
+            .last_room
+            .and_then(|uuid| data.rooms.get(&uuid))
+        {
+            self.room_selection_view
+                .remember(&MessagingMode::Room(Some(room.clone())));
+        }
+        if let Some(mode) = conversation.mode.resolve(data) {
+            self.current_mode = mode;
+        }
+    }
+
+    /// Forgets what is kept about the messages, peers and rooms no longer in the model, the
+    /// UI state stays as large as the model. Nothing is pruned while replaying, the model is
+    /// not the source of the messages.
+    pub fn prune(&mut self, data: &MirroredData) {
+        if data.replay {
+            return;
+        }
+        let present: HashSet<&str> = data.messages.iter().map(|msg| msg.uuid.as_str()).collect();
+        let prompt = &mut self.message_prompt_view;
+        prompt.sent_via.prune(&data.messages);
+        prompt.retry_links.prune(&present);
+        prompt.experiments.prune(&present);
+        self.failed_over
+            .retain(|uuid| present.contains(uuid.as_str()));
+        self.unread.prune(data);
     }
 
     pub fn manage_message(&mut self, data: &MirroredData) {
+        if let Some(conversation) = self.restored_conversation.take() {
+            self.restore_conversation(conversation, data);
+        }
+        let now = DTChatTime::now();
+        self.last_filtering_ms = now.timestamp_millis();
+        let context = &self.pref_ctx.current_context;
         self.messages_to_display = data
             .messages
             .iter()
@@ -184,6 +640,11 @@ impl MessagesView {
                         }
                     }
                 }
+                if !context.status_filter.accepts(msg, &now)
+                    || !context.time_filter.accepts(msg, &now)
+                {
+                    retain = false;
+                }
 
                 match &self.current_mode {
                     MessagingMode::Peer(peer_opt) => {
@@ -216,9 +677,256 @@ impl MessagesView {
             self.pref_ctx.current_context.sort_strategy.clone(),
         );
 
+        self.message_list_view.experiment_tags = self.message_prompt_view.experiments.tags();
+
+        let viewing = self.read_conversation();
+        self.unread.update(data, viewing.as_deref());
+        self.room_selection_view.unread = self.unread.counts().clone();
+        self.message_list_view.unread_divider_ms = self.unread.divider_ms();
+
         // Should be safe as long as those flags are not supposed to be raised asynchronously
     }
 
+    /// Uuid of the peer or room selected, if any
+    pub fn viewed_conversation(&self) -> Option<String> {
+        match &self.current_mode {
+            MessagingMode::Peer(Some(peer)) => Some(peer.uuid.clone()),
+            MessagingMode::Room(Some(room)) => Some(room.uuid.clone()),
+            _ => None,
+        }
+    }
+
+    /// Called every frame: the conversation is only read while the Messages view is shown
+    /// in a focused window
+    pub fn set_reading(&mut self, reading: bool) {
+        if reading && !self.reading {
+            // marks what arrived meanwhile as read
+            self.request_filter = true;
+        }
+        self.reading = reading;
+    }
+
+    /// Uuid of the conversation being read, if any
+    pub fn read_conversation(&self) -> Option<String> {
+        if self.reading {
+            self.viewed_conversation()
+        } else {
+            None
+        }
+    }
+
+    /// Opens the message in the inspector
+    pub fn focus_message(&mut self, msg_uuid: String) {
+        self.message_inspector_view.selected = Some(msg_uuid);
+    }
+
+    /// Switches to the conversation of the message and scrolls the list to it
+    pub fn show_in_conversation(&mut self, msg: &ChatMessage, data: &MirroredData) {
+        self.select_conversation_of(msg, data);
+        self.current_view = MessageViewType::List;
+        self.message_list_view.scroll_to = Some(msg.uuid.clone());
+        self.focus_message(msg.uuid.clone());
+    }
+
+    fn select_conversation_of(&mut self, msg: &ChatMessage, data: &MirroredData) {
+        let uuid = conversation_uuid(msg, data);
+        let mode = match (data.rooms.get(&uuid), data.other_peers.get(&uuid)) {
+            (Some(room), _) => MessagingMode::Room(Some(room.clone())),
+            (None, Some(peer)) => MessagingMode::Peer(Some(peer.clone())),
+            (None, None) => MessagingMode::All,
+        };
+        match &mode {
+            MessagingMode::Peer(Some(peer)) => self.pref_ctx.load_context(&peer.uuid),
+            MessagingMode::Room(Some(room)) => self.pref_ctx.load_context(&room.uuid),
+            _ => self.pref_ctx.load_context("All"),
+        }
+        self.room_selection_view.remember(&mode);
+        self.room_selection_view.active_smart_view = None;
+        self.current_mode = mode;
+        self.request_filter = true;
+    }
+
+    /// Sends the direct messages that just failed again via the next endpoint of the peer,
+    /// when they were sent under the failover policy. Called with the failures of each
+    /// update of the mirrored data.
+    pub fn fail_over(&mut self, failed: &[String], data: &MirroredData) {
+        if data.replay || failed.is_empty() {
+            return;
+        }
+        let now = DTChatTime::now();
+        for msg in data
+            .messages
+            .iter()
+            .filter(|msg| failed.contains(&msg.uuid))
+        {
+            if msg.sender_uuid != data.local_peer.uuid
+                || msg.send_time.timestamp_millis() < self.started_ms
+                || self.failed_over.contains(&msg.uuid)
+            {
+                continue;
+            }
+            let sent_via = &self.message_prompt_view.sent_via;
+            let policy = sent_via.get(&msg.uuid).map(|sent| sent.policy);
+            if policy != Some(DeliveryPolicy::Failover)
+                || !self
+                    .message_prompt_view
+                    .retry_links
+                    .retries_of(&msg.uuid)
+                    .is_empty()
+            {
+                continue;
+            }
+            let Some(peer) = data.other_peers.get(&msg.room_uuid) else {
+                continue;
+            };
+            self.failed_over.insert(msg.uuid.clone());
+            let next =
+                next_failover_endpoint(peer, msg, &self.message_prompt_view.retry_links, sent_via);
+            match next {
+                Some(endpoint) => {
+                    let target = SendTarget::ToPeer(peer.clone(), endpoint);
+                    self.message_prompt_view
+                        .retry(target, msg, &now, DeliveryPolicy::Failover);
+                }
+                None => elog_with_location!(
+                    "Message {} failed on every endpoint of {}",
+                    safe_id_display(&msg.uuid),
+                    peer.name
+                ),
+            }
+        }
+    }
+
+    fn handle_retry_action(
+        &mut self,
+        action: RetryAction,
+        data: &MirroredData,
+        current_time: &DTChatTime,
+    ) {
+        // nothing is sent while replaying a recording
+        if data.replay {
+            return;
+        }
+        let (msg, target) = match action {
+            RetryAction::CopyToComposer(msg) => {
+                if self.current_mode == MessagingMode::All {
+                    self.select_conversation_of(&msg, data);
+                }
+                self.message_prompt_view.set_draft(&msg.content);
+                return;
+            }
+            RetryAction::RetryVia(msg, endpoint) => {
+                let target = data
+                    .other_peers
+                    .get(&msg.room_uuid)
+                    .map(|peer| SendTarget::ToPeer(peer.clone(), endpoint));
+                (msg, target)
+            }
+            RetryAction::Retry(msg) => {
+                let target = match (
+                    data.rooms.get(&msg.room_uuid),
+                    data.other_peers.get(&msg.room_uuid),
+                ) {
+                    (Some(room), _) => Some(SendTarget::ToRoom(room.clone())),
+                    // same endpoint as the failed attempt when the peer still has it
+                    (None, Some(peer)) => {
+                        let sent = self.message_prompt_view.sent_via.get(&msg.uuid);
+                        peer.endpoints
+                            .iter()
+                            .find(|endpoint| {
+                                sent.is_some_and(|sent| sent.endpoint.matches(endpoint))
+                            })
+                            .or(peer.endpoints.first())
+                            .map(|endpoint| SendTarget::ToPeer(peer.clone(), endpoint.clone()))
+                    }
+                    (None, None) => None,
+                };
+                (msg, target)
+            }
+        };
+        match target {
+            Some(target) => {
+                let policy = self.pref_ctx.delivery_policy(&msg.room_uuid);
+                self.message_prompt_view
+                    .retry(target, &msg, current_time, policy);
+            }
+            None => elog_with_location!(
+                "No peer or room to retry message {}",
+                safe_id_display(&msg.uuid)
+            ),
+        }
+    }
+
+    fn handle_smart_view_action(&mut self, action: SmartViewAction, data: &MirroredData) {
+        let side_view = &mut self.room_selection_view;
+        match action {
+            SmartViewAction::Apply(idx) => {
+                let Some(view) = side_view.smart_views.get(idx).cloned() else {
+                    return;
+                };
+                let mode = view.messaging_mode(data);
+                match &mode {
+                    MessagingMode::Peer(Some(peer)) => self.pref_ctx.load_context(&peer.uuid),
+                    MessagingMode::Room(Some(room)) => self.pref_ctx.load_context(&room.uuid),
+                    _ => self.pref_ctx.load_context("All"),
+                }
+                // the stored preferences of the conversation are left as they are
+                self.pref_ctx.overlay(|context| {
+                    context.protocol_filter = view.protocol_filter();
+                    context.status_filter = view.status_filter();
+                    context.time_filter = view.time_filter;
+                    context.sort_strategy = view.sort_strategy();
+                });
+                self.message_search_view.query = view.search.clone();
+                side_view.remember(&mode);
+                side_view.active_smart_view = Some(idx);
+                self.current_mode = mode;
+                self.request_filter = true;
+            }
+            SmartViewAction::SaveCurrent(name) => {
+                let context = &self.pref_ctx.current_context;
+                let view = SmartView::new(
+                    name,
+                    &self.current_mode,
+                    &context.protocol_filter,
+                    &context.status_filter,
+                    &context.time_filter,
+                    &context.sort_strategy,
+                    &self.message_search_view.query,
+                );
+                // saving under an existing name replaces the view
+                let idx = match side_view
+                    .smart_views
+                    .iter()
+                    .position(|v| v.name == view.name)
+                {
+                    Some(idx) => {
+                        side_view.smart_views[idx] = view;
+                        idx
+                    }
+                    None => {
+                        side_view.smart_views.push(view);
+                        side_view.smart_views.len() - 1
+                    }
+                };
+                side_view.active_smart_view = Some(idx);
+            }
+            SmartViewAction::Delete(idx) => {
+                if idx < side_view.smart_views.len() {
+                    side_view.smart_views.remove(idx);
+                }
+                match side_view.active_smart_view {
+                    Some(active) if active == idx => {
+                        side_view.active_smart_view = None;
+                        self.request_filter |= self.pref_ctx.end_overlay();
+                    }
+                    Some(active) if active > idx => side_view.active_smart_view = Some(active - 1),
+                    _ => {}
+                }
+            }
+        }
+    }
+
     fn message_to_display_bounds(&mut self) -> usize {
         let msgs = self.messages_to_display.len();
         match self.pref_ctx.current_context.max_message_count {
@@ -235,28 +943,46 @@ impl MessagesView {
         current_time: &DTChatTime,
         ui: &mut Ui,
     ) {
+        let context = &self.pref_ctx.current_context;
+        if (context.time_filter.is_relative() || context.status_filter == StatusFilter::Overdue)
+            && current_time.timestamp_millis() - self.last_filtering_ms >= 1000
+        {
+            self.request_filter = true;
+        }
         if self.request_filter {
             self.manage_message(data);
             self.request_filter = false;
         }
 
         TopBottomPanel::bottom("message_forge_panel").show_inside(ui, |ui| {
+            if data.replay {
+                ui.add_space(8.0);
+                ui.colored_label(
+                    Color32::YELLOW,
+                    "Replaying a recording: sending is disabled",
+                );
+                ui.add_space(8.0);
+                return;
+            }
             self.message_prompt_view.show(
                 ctx,
                 ui,
                 &mut self.pref_ctx.current_context.proto,
-                data.pbat_support_by_model,
+                &mut self.pref_ctx.current_context.delivery_policy,
+                data,
                 &self.current_mode,
+                current_time,
             );
         });
 
         let start_idx: usize = self.message_to_display_bounds();
 
-        egui::SidePanel::right("right_panel")
+        let side_panel = egui::SidePanel::right("right_panel")
             .resizable(true)
-            .default_width(115.0)
+            .default_width(self.side_panel_width)
             .width_range(80.0..=200.0)
             .show_inside(ui, |ui| {
+                let filter_requested = self.request_filter;
                 self.room_selection_view.show(
                     ui,
                     &data.other_peers,
@@ -264,26 +990,82 @@ impl MessagesView {
                     &mut self.pref_ctx,
                     &mut self.current_mode,
                     &mut self.request_filter,
-                )
+                );
+                // another conversation was selected
+                if self.request_filter && !filter_requested {
+                    self.room_selection_view.active_smart_view = None;
+                }
             });
+        if let Some(action) = self.room_selection_view.smart_view_action.take() {
+            self.handle_smart_view_action(action, data);
+        }
+
+        self.side_panel_width = side_panel.response.rect.width();
+
+        if self.message_inspector_view.selected.is_some() {
+            let inspector = egui::SidePanel::right("inspector_panel")
+                .resizable(true)
+                .default_width(self.inspector_width)
+                .width_range(180.0..=500.0)
+                .show_inside(ui, |ui| {
+                    self.message_inspector_view.show(
+                        ui,
+                        &data.messages,
+                        &data.local_peer,
+                        &data.other_peers,
+                        &data.rooms,
+                        &data.history,
+                        &data.socket_log,
+                        &self.message_prompt_view.retry_links,
+                        &self.message_prompt_view.sent_via,
+                    )
+                });
+            self.inspector_width = inspector.response.rect.width();
+        }
 
         // setting + message view (graph/list/etc.)
         CentralPanel::default().show_inside(ui, |ui| {
+            let mut search_result = None;
+            TopBottomPanel::top("message_search_bar").show_inside(ui, |ui| {
+                search_result = self.message_search_view.show(ui, data);
+            });
+            self.message_list_view.highlight =
+                self.message_search_view.active_query().map(str::to_string);
+            if let Some(msg) = search_result {
+                self.show_in_conversation(&msg, data);
+            }
+
             TopBottomPanel::top("message_settings_bar").show_inside(ui, |ui| {
-                self.message_settings_view.show(
-                    ui,
-                    &mut self.current_view,
-                    &mut self.pref_ctx.current_context.sort_strategy,
-                    &mut self.pref_ctx.current_context.protocol_filter,
-                    &mut self.pref_ctx.current_context.max_message_count,
-                    self.messages_to_display.len(),
-                    &data.local_peer,
-                    &data.other_peers,
-                    &mut self.request_filter,
-                );
+                ui.horizontal(|ui| {
+                    self.message_settings_view.show(
+                        ui,
+                        &mut self.current_view,
+                        &mut self.pref_ctx.current_context.sort_strategy,
+                        &mut self.pref_ctx.current_context.protocol_filter,
+                        &mut self.pref_ctx.current_context.max_message_count,
+                        self.messages_to_display.len(),
+                        &data.local_peer,
+                        &data.other_peers,
+                        &mut self.request_filter,
+                    );
+                    ui.separator();
+                    self.message_settings_view.show_filters(
+                        ui,
+                        &mut self.pref_ctx.current_context.status_filter,
+                        &mut self.pref_ctx.current_context.time_filter,
+                        &mut self.request_filter,
+                    );
+                    ui.separator();
+                    self.message_export_view.show(
+                        ui,
+                        &self.messages_to_display[start_idx..],
+                        data,
+                        &self.message_prompt_view.experiments,
+                    );
+                });
             });
             match self.current_view {
-                MessageViewType::MessageGraph => {
+                MessageViewType::Graph => {
                     self.message_graph_view.show(
                         ctx,
                         ui,
@@ -291,18 +1073,48 @@ impl MessagesView {
                         &data.local_peer,
                         &data.other_peers,
                         current_time,
+                        data.contact_plan.as_ref(),
+                        &data.peer_colors,
+                        &mut self.message_inspector_view.selected,
                     );
                 }
-                MessageViewType::MessageList => {
+                MessageViewType::List => {
+                    // the list is filtered again before it shows a message just selected
+                    if !self.request_filter {
+                        self.message_list_view
+                            .forget_unlisted_target(&self.messages_to_display[start_idx..]);
+                    }
                     self.message_list_view.show(
                         ui,
                         &self.messages_to_display[start_idx..],
                         current_time,
                         &data.local_peer,
                         &data.other_peers,
+                        &mut self.message_inspector_view.selected,
+                        &data.history,
+                        &self.message_prompt_view.retry_links,
+                        &self.message_prompt_view.sent_via,
+                    );
+                }
+                MessageViewType::Sequence => {
+                    self.message_sequence_view.show(
+                        ui,
+                        &self.messages_to_display[start_idx..],
+                        &data.local_peer,
+                        &data.other_peers,
+                        current_time,
                     );
                 }
             }
         });
+
+        let retry_action = self
+            .message_list_view
+            .retry_action
+            .take()
+            .or_else(|| self.message_graph_view.retry_action.take());
+        if let Some(action) = retry_action {
+            self.handle_retry_action(action, data, current_time);
+        }
     }
 }
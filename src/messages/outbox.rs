@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use chrono::{Duration, Local, NaiveTime, TimeZone};
+use dtchat_backend::dtchat::{ChatModel, Peer, Room};
+use dtchat_backend::message::{ChatMessage, Content};
+use dtchat_backend::time::DTChatTime;
+use dtchat_backend::Endpoint;
+use eframe::egui;
+use egui::{Color32, RichText, Ui};
+
+use crate::messages::delivery::DeliveryPolicy;
+use crate::utils::config::ParticipantConfig;
+use crate::utils::contact_plan::{configured_node, ipn_node, peer_nodes, ContactPlan};
+use crate::utils::font::{pretty_duration, PrettyStr};
+
+#[derive(Clone)]
+pub enum SendTarget {
+    ToRoom(Room),
+    ToPeer(Peer, Endpoint),
+}
+
+impl SendTarget {
+    pub fn label(&self) -> String {
+        match self {
+            SendTarget::ToRoom(room) => format!("room {}", room.name),
+            SendTarget::ToPeer(peer, endpoint) => {
+                format!("{} via {}", peer.name, endpoint.to_pretty_str())
+            }
+        }
+    }
+
+    /// Uuid of the room the model files the message under, the peer for a direct message
+    fn room_uuid(&self) -> &str {
+        match self {
+            SendTarget::ToRoom(room) => &room.uuid,
+            SendTarget::ToPeer(peer, _) => &peer.uuid,
+        }
+    }
+
+    /// Hands the message to the model and returns the uuid of the message it created. The
+    /// model is locked by the caller: the new message is the last one of the local peer
+    /// for this room after the cursor.
+    pub fn send(
+        &self,
+        model: &mut ChatModel,
+        content: &Content,
+        pbat: bool,
+        cursor: &mut MessageCursor,
+    ) -> Option<String> {
+        let local_uuid = model.get_localpeer().uuid;
+        let before = match cursor.known {
+            Some(count) => count,
+            None => model.get_all_messages().len(),
+        };
+        match self {
+            SendTarget::ToRoom(room) => {
+                model.send_to_room(content, &room.uuid, pbat);
+            }
+            SendTarget::ToPeer(peer, endpoint) => {
+                model.send_to_peer(
+                    content,
+                    &peer.uuid.clone(),
+                    peer.uuid.clone(),
+                    endpoint,
+                    pbat,
+                );
+            }
+        }
+        let messages = model.get_all_messages();
+        cursor.known = Some(messages.len());
+        messages
+            .into_iter()
+            .skip(before)
+            .rev()
+            .find(|msg| msg.sender_uuid == local_uuid && msg.room_uuid == self.room_uuid())
+            .map(|msg| msg.uuid)
+    }
+
+    /// Peers the message is for, the room participants come from the configuration
+    fn recipients<'a>(
+        &self,
+        local_peer: &Peer,
+        other_peers: &'a HashMap<String, Peer>,
+        room_participants: &HashMap<String, Vec<ParticipantConfig>>,
+    ) -> Vec<&'a Peer> {
+        match self {
+            SendTarget::ToPeer(peer, _) => other_peers.get(&peer.uuid).into_iter().collect(),
+            SendTarget::ToRoom(room) => match room_participants.get(&room.uuid) {
+                Some(participants) => participants
+                    .iter()
+                    .filter(|participant| participant.peer_uuid != local_peer.uuid)
+                    .filter_map(|participant| other_peers.get(&participant.peer_uuid))
+                    .collect(),
+                None => other_peers.values().collect(),
+            },
+        }
+    }
+
+    /// Bundle node the message travels to, by recipient name: the node of the chosen
+    /// endpoint for a direct message, the node of the room endpoint of each participant for
+    /// a room. None for the participants reached over tcp or udp, or whose endpoint is not
+    /// configured. None for a direct message over tcp or udp, which does not follow the
+    /// contact plan.
+    pub fn recipient_nodes(
+        &self,
+        local_peer: &Peer,
+        other_peers: &HashMap<String, Peer>,
+        room_participants: &HashMap<String, Vec<ParticipantConfig>>,
+    ) -> Option<Vec<(String, Option<u64>)>> {
+        match self {
+            SendTarget::ToPeer(peer, endpoint) => {
+                let node = ipn_node(endpoint)?;
+                Some(vec![(peer.name.clone(), Some(node))])
+            }
+            SendTarget::ToRoom(room) => Some(match room_participants.get(&room.uuid) {
+                Some(participants) => participants
+                    .iter()
+                    .filter(|participant| participant.peer_uuid != local_peer.uuid)
+                    .filter_map(|participant| {
+                        let peer = other_peers.get(&participant.peer_uuid)?;
+                        let node = participant.endpoint.as_deref().and_then(configured_node);
+                        Some((peer.name.clone(), node))
+                    })
+                    .collect(),
+                // the backend chooses the endpoints
+                None => other_peers
+                    .values()
+                    .map(|peer| (peer.name.clone(), None))
+                    .collect(),
+            }),
+        }
+    }
+}
+
+/// Arrival of a message at one recipient, along the contact plan
+#[derive(Clone, Copy)]
+pub enum ArrivalEstimate {
+    At(DTChatTime),
+    // no contact path to the recipient
+    NoRoute,
+    // reached over tcp or udp, which do not follow the contact plan
+    NoEstimate,
+}
+
+/// Count of the messages of the model after the last send. The model appends the messages,
+/// those created by the next send are after it: only the tail is searched for them, the
+/// messages received meanwhile are of other peers.
+#[derive(Default)]
+pub struct MessageCursor {
+    known: Option<usize>,
+}
+
+impl MessageCursor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Start of the next contact from the local node to one of the recipients reached as
+/// bundles, `now` when a contact is already open. When none is, as for a direct message over
+/// tcp or udp, any contact with the recipients.
+pub fn next_contact(
+    plan: &ContactPlan,
+    target: &SendTarget,
+    local_peer: &Peer,
+    other_peers: &HashMap<String, Peer>,
+    room_participants: &HashMap<String, Vec<ParticipantConfig>>,
+    now: &DTChatTime,
+) -> Option<DTChatTime> {
+    let from_nodes = peer_nodes(local_peer);
+    let mut to_nodes: Vec<u64> = target
+        .recipient_nodes(local_peer, other_peers, room_participants)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(_, node)| node)
+        .collect();
+    if to_nodes.is_empty() {
+        to_nodes = target
+            .recipients(local_peer, other_peers, room_participants)
+            .into_iter()
+            .flat_map(peer_nodes)
+            .collect();
+    }
+    let start = plan.next_contact_start(&from_nodes, &to_nodes, now.timestamp_millis())?;
+    DTChatTime::from_timestamp_millis(start)
+}
+
+/// Earliest arrival along the contact plan at each recipient (name, arrival) for a message
+/// handed over at `send_at`, an estimate rather than the A-SABR prediction of the backend.
+/// None when the message does not travel as a bundle.
+pub fn estimate_arrivals(
+    plan: &ContactPlan,
+    target: &SendTarget,
+    local_peer: &Peer,
+    other_peers: &HashMap<String, Peer>,
+    room_participants: &HashMap<String, Vec<ParticipantConfig>>,
+    send_at: &DTChatTime,
+) -> Option<Vec<(String, ArrivalEstimate)>> {
+    let from_nodes = peer_nodes(local_peer);
+    let recipients = target.recipient_nodes(local_peer, other_peers, room_participants)?;
+    Some(
+        recipients
+            .into_iter()
+            .map(|(name, node)| {
+                let Some(node) = node else {
+                    return (name, ArrivalEstimate::NoEstimate);
+                };
+                let arrival = plan
+                    .earliest_arrival(&from_nodes, &[node], send_at.timestamp_millis())
+                    .and_then(DTChatTime::from_timestamp_millis);
+                match arrival {
+                    Some(at) => (name, ArrivalEstimate::At(at)),
+                    None => (name, ArrivalEstimate::NoRoute),
+                }
+            })
+            .collect(),
+    )
+}
+
+/// "14:30" or "14:30:15", local time, today or tomorrow if already past
+pub fn parse_local_time(text: &str, now: &DTChatTime) -> Option<DTChatTime> {
+    let time = NaiveTime::parse_from_str(text.trim(), "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(text.trim(), "%H:%M"))
+        .ok()?;
+    let now_ms = now.timestamp_millis();
+    let today = Local
+        .timestamp_millis_opt(now_ms)
+        .single()?
+        .date_naive()
+        .and_time(time);
+    let mut at = Local.from_local_datetime(&today).earliest()?;
+    if at.timestamp_millis() < now_ms {
+        at += Duration::days(1);
+    }
+    DTChatTime::from_timestamp_millis(at.timestamp_millis())
+}
+
+fn local_time_str(time: &DTChatTime) -> String {
+    match Local.timestamp_millis_opt(time.timestamp_millis()).single() {
+        Some(dt) => dt.format("%H:%M:%S").to_string(),
+        None => "???".to_string(),
+    }
+}
+
+pub struct ScheduledMessage {
+    pub id: u64,
+    pub target: SendTarget,
+    pub text: String,
+    pub file: Option<PathBuf>,
+    pub pbat: bool,
+    pub send_at: DTChatTime,
+    // why this time was chosen, e.g. "next contact"
+    pub reason: String,
+    // uuid of the failed message this one sends again
+    pub retry_of: Option<String>,
+    // delivery policy of the peer when the message was scheduled
+    pub policy: DeliveryPolicy,
+}
+
+impl ScheduledMessage {
+    pub fn content(&self) -> Content {
+        match &self.file {
+            Some(file) => Content::File(file.to_string_lossy().to_string()),
+            None => Content::Text(self.text.clone()),
+        }
+    }
+}
+
+/// Messages waiting for their sending time
+#[derive(Default)]
+pub struct Outbox {
+    queue: Vec<ScheduledMessage>,
+    next_id: u64,
+    // send time being edited, by message id
+    time_inputs: HashMap<u64, String>,
+}
+
+impl Outbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn schedule(
+        &mut self,
+        target: SendTarget,
+        text: String,
+        file: Option<PathBuf>,
+        pbat: bool,
+        send_at: DTChatTime,
+        reason: &str,
+        policy: DeliveryPolicy,
+    ) {
+        self.push(ScheduledMessage {
+            id: self.next_id,
+            target,
+            text,
+            file,
+            pbat,
+            send_at,
+            reason: reason.to_string(),
+            retry_of: None,
+            policy,
+        });
+    }
+
+    /// Sends the content of a failed message again at `send_at`
+    pub fn schedule_retry(
+        &mut self,
+        target: SendTarget,
+        original: &ChatMessage,
+        pbat: bool,
+        send_at: DTChatTime,
+        policy: DeliveryPolicy,
+    ) {
+        let (text, file) = match &original.content {
+            Content::Text(text) => (text.clone(), None),
+            Content::File(path) => (String::new(), Some(PathBuf::from(path))),
+        };
+        self.push(ScheduledMessage {
+            id: self.next_id,
+            target,
+            text,
+            file,
+            pbat,
+            send_at,
+            reason: "retry".to_string(),
+            retry_of: Some(original.uuid.clone()),
+            policy,
+        });
+    }
+
+    fn push(&mut self, msg: ScheduledMessage) {
+        self.queue.push(msg);
+        self.next_id += 1;
+        self.queue.sort_by_key(|m| m.send_at.timestamp_millis());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Removes and returns the messages whose time has come
+    pub fn take_due(&mut self, now: &DTChatTime) -> Vec<ScheduledMessage> {
+        let now_ms = now.timestamp_millis();
+        let (due, waiting) = std::mem::take(&mut self.queue)
+            .into_iter()
+            .partition(|m| m.send_at.timestamp_millis() <= now_ms);
+        self.queue = waiting;
+        for msg in &due {
+            self.time_inputs.remove(&msg.id);
+        }
+        due
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, now: &DTChatTime) {
+        let now_ms = now.timestamp_millis();
+        let mut cancelled = None;
+        let mut send_now = None;
+
+        egui::CollapsingHeader::new(format!("\u{23F0} Outgoing queue ({})", self.queue.len()))
+            .id_salt("outbox")
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new("outbox_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for msg in self.queue.iter_mut() {
+                            ui.label(RichText::new(msg.target.label()).color(Color32::GRAY));
+
+                            let input = self
+                                .time_inputs
+                                .entry(msg.id)
+                                .or_insert_with(|| local_time_str(&msg.send_at));
+                            let response = ui.add(
+                                egui::TextEdit::singleline(input)
+                                    .desired_width(70.0)
+                                    .hint_text("HH:MM:SS"),
+                            );
+                            if response.lost_focus() {
+                                match parse_local_time(input, now) {
+                                    Some(at) => {
+                                        msg.send_at = at;
+                                        msg.reason = "edited".to_string();
+                                    }
+                                    None => *input = local_time_str(&msg.send_at),
+                                }
+                            }
+                            response.on_hover_text(format!(
+                                "{}, in {}",
+                                msg.reason,
+                                pretty_duration(msg.send_at.timestamp_millis() - now_ms)
+                            ));
+
+                            match &msg.file {
+                                Some(file) => {
+                                    ui.label(format!("\u{1F4C1} {}", file.to_string_lossy()));
+                                }
+                                None => {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut msg.text)
+                                            .desired_width(200.0),
+                                    );
+                                }
+                            }
+
+                            ui.horizontal(|ui| {
+                                if ui.button("Send now").clicked() {
+                                    send_now = Some(msg.id);
+                                }
+                                if ui
+                                    .button(RichText::new("\u{2716}").color(Color32::RED))
+                                    .on_hover_text("Cancel")
+                                    .clicked()
+                                {
+                                    cancelled = Some(msg.id);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(id) = cancelled {
+            self.queue.retain(|m| m.id != id);
+            self.time_inputs.remove(&id);
+        }
+        if let Some(id) = send_now {
+            if let Some(msg) = self.queue.iter_mut().find(|m| m.id == id) {
+                msg.send_at = *now;
+            }
+        }
+        self.queue.sort_by_key(|m| m.send_at.timestamp_millis());
+    }
+}
@@ -1,8 +1,20 @@
+use crate::elog_with_location;
+use crate::main_view::MirroredData;
+use crate::messages::delivery::{best_endpoint, DeliveryPolicy, SentEndpoints};
+use crate::messages::experiment::ExperimentLog;
+use crate::messages::outbox::{
+    estimate_arrivals, next_contact, parse_local_time, ArrivalEstimate, MessageCursor, Outbox,
+    SendTarget,
+};
+use crate::messages::retry::RetryLinks;
 use crate::messages::MessagingMode;
-use crate::utils::font::PrettyStr;
-use dtchat_backend::dtchat::{ChatModel, Peer, Room};
-use dtchat_backend::message::Content;
-use dtchat_backend::Endpoint;
+use crate::utils::contact_plan::ContactPlan;
+use crate::utils::font::{pretty_duration, PrettyStr};
+use crate::utils::uuid::safe_id_display;
+use dtchat_backend::dtchat::ChatModel;
+use dtchat_backend::message::{ChatMessage, Content};
+use dtchat_backend::time::DTChatTime;
+use dtchat_backend::{Endpoint, EndpointProto};
 use eframe::egui;
 use egui::{ComboBox, RichText, Ui};
 use egui_file_dialog::FileDialog;
@@ -15,12 +27,87 @@ pub struct MessagePromptView {
     pbat_enabled: bool,
     file_dialog: FileDialog,
     picked_file: Option<PathBuf>,
+    pub outbox: Outbox,
+    schedule_input: String,
+    // send to every endpoint of the peer at once
+    experiment_mode: bool,
+    pub experiments: ExperimentLog,
+    // retries sent from the outgoing queue, linked to the message they send again
+    pub retry_links: RetryLinks,
+    // endpoint of each direct message sent
+    pub sent_via: SentEndpoints,
+    send_cursor: MessageCursor,
 }
 
-#[derive(Clone)]
-enum PrepareSend {
-    ToRoom(Room),
-    ToPeer(Peer, Endpoint),
+/// Hands the message to the model and records the endpoint of a direct message, with the
+/// delivery policy it is sent under
+fn send_and_record(
+    model: &mut ChatModel,
+    target: &SendTarget,
+    content: &Content,
+    pbat: bool,
+    policy: DeliveryPolicy,
+    sent_via: &mut SentEndpoints,
+    cursor: &mut MessageCursor,
+) -> Option<String> {
+    let uuid = target.send(model, content, pbat, cursor)?;
+    if let SendTarget::ToPeer(_, endpoint) = target {
+        sent_via.record(&uuid, endpoint, policy);
+    }
+    Some(uuid)
+}
+
+/// "12:34:56 (+5 min 00 s)" for the last recipient to receive the message as a bundle,
+/// with the detail per recipient as hover text
+fn arrival_summary(
+    arrivals: &[(String, ArrivalEstimate)],
+    send_at: &DTChatTime,
+) -> (String, String) {
+    let details = arrivals
+        .iter()
+        .map(|(name, arrival)| match arrival {
+            ArrivalEstimate::At(at) => format!(
+                "{}: {}",
+                name,
+                at.ts_to_str(false, true, None, &chrono::Local)
+            ),
+            ArrivalEstimate::NoRoute => format!("{}: no route", name),
+            ArrivalEstimate::NoEstimate => format!("{}: no estimate (not over bp)", name),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if arrivals.is_empty() {
+        return ("no recipient".to_string(), details);
+    }
+    if arrivals
+        .iter()
+        .any(|(_, arrival)| matches!(arrival, ArrivalEstimate::NoRoute))
+    {
+        return ("no route".to_string(), details);
+    }
+    let Some(last) = arrivals
+        .iter()
+        .filter_map(|(_, arrival)| match arrival {
+            ArrivalEstimate::At(at) => Some(*at),
+            _ => None,
+        })
+        .max_by_key(|at| at.timestamp_millis())
+    else {
+        return ("no estimate".to_string(), details);
+    };
+    let mut summary = format!(
+        "{} (+{})",
+        last.ts_to_str(false, true, None, &chrono::Local),
+        pretty_duration(last.timestamp_millis() - send_at.timestamp_millis())
+    );
+    if arrivals
+        .iter()
+        .any(|(_, arrival)| matches!(arrival, ArrivalEstimate::NoEstimate))
+    {
+        summary.push_str(", some without estimate");
+    }
+    (summary, details)
 }
 
 impl MessagePromptView {
@@ -31,17 +118,84 @@ impl MessagePromptView {
             pbat_enabled: false,
             file_dialog: FileDialog::new(),
             picked_file: None,
+            outbox: Outbox::new(),
+            schedule_input: String::new(),
+            experiment_mode: false,
+            experiments: ExperimentLog::new(),
+            retry_links: RetryLinks::new(),
+            sent_via: SentEndpoints::new(),
+            send_cursor: MessageCursor::new(),
         }
     }
 
+    /// Puts the content of a message back in the composer
+    pub fn set_draft(&mut self, content: &Content) {
+        match content {
+            Content::Text(text) => {
+                self.picked_file = None;
+                self.input_text = text.clone();
+            }
+            Content::File(path) => {
+                self.picked_file = Some(PathBuf::from(path));
+                self.input_text = format!("Send file {}", path);
+            }
+        }
+    }
+
+    /// Sends the content of a failed message again, through the outgoing queue. The retry
+    /// is linked to it once the model created its message.
+    pub fn retry(
+        &mut self,
+        target: SendTarget,
+        msg: &ChatMessage,
+        now: &DTChatTime,
+        policy: DeliveryPolicy,
+    ) {
+        let pbat = msg.predicted_arrival_time.is_some();
+        self.outbox.schedule_retry(target, msg, pbat, *now, policy);
+    }
+
+    /// Hands the scheduled messages whose time has come to the model
+    pub fn send_scheduled(&mut self, now: &DTChatTime) {
+        let due = self.outbox.take_due(now);
+        if due.is_empty() {
+            return;
+        }
+        if let Ok(mut model) = self.model.lock() {
+            for msg in due {
+                let uuid = send_and_record(
+                    &mut model,
+                    &msg.target,
+                    &msg.content(),
+                    msg.pbat,
+                    msg.policy,
+                    &mut self.sent_via,
+                    &mut self.send_cursor,
+                );
+                match (uuid, &msg.retry_of) {
+                    (Some(uuid), Some(original)) => self.retry_links.link(&uuid, original),
+                    (None, Some(original)) => elog_with_location!(
+                        "The retry of message {} did not create a message",
+                        safe_id_display(original)
+                    ),
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn show(
         &mut self,
         ctx: &egui::Context,
         ui: &mut egui::Ui,
         proto_for_peer: &mut Option<Endpoint>,
-        pbat_support_by_model: bool,
+        delivery_policy: &mut DeliveryPolicy,
+        data: &MirroredData,
         current_mode: &MessagingMode,
+        current_time: &DTChatTime,
     ) {
+        let pbat_support_by_model = data.pbat_support_by_model;
         let mut prepare_send = None;
 
         self.file_dialog.update(ctx);
@@ -59,33 +213,77 @@ impl MessagePromptView {
                     if proto_for_peer.is_none() && peer_has_endpoints {
                         *proto_for_peer = Some(peer.endpoints[0].clone());
                     }
+                    let best = match delivery_policy {
+                        DeliveryPolicy::BestByHistory => {
+                            best_endpoint(peer, &data.messages, &data.local_peer, &self.sent_via)
+                        }
+                        _ => None,
+                    };
+                    // the best endpoint is used as long as the policy is on, the selected
+                    // one is kept for when it is turned off
+                    let endpoint = match &best {
+                        Some((endpoint, _)) => Some(endpoint),
+                        None => proto_for_peer.as_ref(),
+                    };
 
-                    let selected_text = match &proto_for_peer {
+                    let selected_text = match endpoint {
                         Some(endpoint) => {
-                            prepare_send =
-                                Some(PrepareSend::ToPeer(peer.clone(), endpoint.clone()));
+                            prepare_send = Some(SendTarget::ToPeer(peer.clone(), endpoint.clone()));
                             endpoint.to_pretty_str()
                         }
                         None => "⚠ no endpoints".to_string(),
                     };
                     ui.label("Select target endpoint: ");
-                    ComboBox::from_id_salt("protocol_selector")
-                        .selected_text(selected_text.clone())
-                        .show_ui(ui, |ui| {
-                            for endpoint in &peer.endpoints {
-                                let is_selected = selected_text == *endpoint.to_pretty_str();
-                                if ui
-                                    .selectable_label(is_selected, endpoint.to_pretty_str())
-                                    .clicked()
-                                {
-                                    *proto_for_peer = Some(endpoint.clone());
+                    ui.add_enabled_ui(best.is_none() && !self.experiment_mode, |ui| {
+                        ComboBox::from_id_salt("protocol_selector")
+                            .selected_text(selected_text.clone())
+                            .show_ui(ui, |ui| {
+                                for endpoint in &peer.endpoints {
+                                    let is_selected = selected_text == *endpoint.to_pretty_str();
+                                    if ui
+                                        .selectable_label(is_selected, endpoint.to_pretty_str())
+                                        .clicked()
+                                    {
+                                        *proto_for_peer = Some(endpoint.clone());
+                                    }
                                 }
+                            });
+                    });
+                    ui.separator();
+                    ui.label("Delivery: ");
+                    ComboBox::from_id_salt("delivery_policy")
+                        .selected_text(delivery_policy.to_pretty_str())
+                        .show_ui(ui, |ui| {
+                            for policy in DeliveryPolicy::ALL {
+                                ui.selectable_value(
+                                    delivery_policy,
+                                    policy,
+                                    policy.to_pretty_str(),
+                                );
                             }
                         });
+                    match (*delivery_policy, &best) {
+                        (DeliveryPolicy::Failover, _) => {
+                            ui.colored_label(
+                                egui::Color32::GRAY,
+                                "next endpoints tried on failure",
+                            );
+                        }
+                        (DeliveryPolicy::BestByHistory, Some((_, delay))) => {
+                            ui.colored_label(
+                                egui::Color32::GRAY,
+                                format!("median delay {}", pretty_duration(*delay)),
+                            );
+                        }
+                        (DeliveryPolicy::BestByHistory, None) => {
+                            ui.colored_label(egui::Color32::GRAY, "no delay observed yet");
+                        }
+                        (DeliveryPolicy::Manual, _) => (),
+                    }
                 });
             }
             MessagingMode::Room(Some(room)) => {
-                prepare_send = Some(PrepareSend::ToRoom(room.clone()));
+                prepare_send = Some(SendTarget::ToRoom(room.clone()));
                 ui.label(format!("To room \"{}\"", room.name));
             }
             _ => {
@@ -93,6 +291,23 @@ impl MessagePromptView {
             }
         });
         ui.add_space(8.0);
+        // sent with the message, the failover depends on it
+        let policy = *delivery_policy;
+
+        if !self.outbox.is_empty() {
+            self.outbox.show(ui, current_time);
+            ui.add_space(8.0);
+        }
+        if !self.experiments.is_empty() {
+            self.experiments.show(ui, &data.messages);
+            ui.add_space(8.0);
+        }
+
+        // experiments are only sent to a peer
+        let experiment_peer = match &prepare_send {
+            Some(SendTarget::ToPeer(peer, _)) if self.experiment_mode => Some(peer.clone()),
+            _ => None,
+        };
 
         ui.horizontal(|ui| {
             // we want to keep prepare_send for footer
@@ -102,7 +317,7 @@ impl MessagePromptView {
                 |ui: &mut Ui| {
                     let text_edit = egui::TextEdit::singleline(&mut self.input_text)
                         .hint_text("Type your message...")
-                        .desired_width(ui.available_width() - 24.0 - 60.0 - 24.0)
+                        .desired_width(ui.available_width() - 24.0 - 60.0 - 24.0 - 32.0)
                         .margin(egui::Margin::same(6));
 
                     let response = ui.add(text_edit);
@@ -147,23 +362,112 @@ impl MessagePromptView {
                 response
             });
 
+            let mut schedule = None;
+            ui.add_enabled_ui(prepare_send.is_some() && experiment_peer.is_none(), |ui| {
+                ui.menu_button("\u{23F0}", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Send at");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.schedule_input)
+                                .hint_text("HH:MM:SS")
+                                .desired_width(70.0),
+                        );
+                        let send_at = parse_local_time(&self.schedule_input, current_time);
+                        if ui
+                            .add_enabled(send_at.is_some(), egui::Button::new("Schedule"))
+                            .on_disabled_hover_text("Local time, e.g. 14:30 or 14:30:15")
+                            .clicked()
+                        {
+                            schedule = send_at.map(|at| (at, "scheduled"));
+                        }
+                    });
+
+                    let contact = match (&data.contact_plan, &prepare_send) {
+                        (Some(plan), Some(target)) => next_contact(
+                            plan,
+                            target,
+                            &data.local_peer,
+                            &data.other_peers,
+                            &data.room_participants,
+                            current_time,
+                        )
+                        .ok_or("No upcoming contact in the contact plan"),
+                        _ => Err("No contact plan loaded"),
+                    };
+                    match contact {
+                        Ok(at) => {
+                            let text = format!(
+                                "Send at next contact ({})",
+                                at.ts_to_str(false, true, None, &chrono::Local)
+                            );
+                            if ui.button(text).clicked() {
+                                schedule = Some((at, "next contact"));
+                            }
+                        }
+                        Err(reason) => {
+                            ui.add_enabled(false, egui::Button::new("Send at next contact"))
+                                .on_disabled_hover_text(reason);
+                        }
+                    }
+
+                    if schedule.is_some() {
+                        ui.close_menu();
+                    }
+                })
+                .response
+                .on_hover_text("Schedule the message");
+            });
+
+            if let (Some((send_at, reason)), Some(target)) = (schedule, prepare_send.clone()) {
+                self.outbox.schedule(
+                    target,
+                    self.input_text.clone(),
+                    self.picked_file.take(),
+                    self.pbat_enabled,
+                    send_at,
+                    reason,
+                    policy,
+                );
+                self.input_text.clear();
+                self.schedule_input.clear();
+            }
+
             if let Some(to_send) = must_send {
                 if let Ok(mut model) = self.model.lock() {
                     let content = match &self.picked_file {
                         Some(file) => Content::File(file.to_string_lossy().to_string()),
                         None => Content::Text(self.input_text.to_string()),
                     };
-                    match to_send {
-                        PrepareSend::ToRoom(room) => {
-                            model.send_to_room(&content, &room.uuid, self.pbat_enabled);
+                    match &experiment_peer {
+                        Some(peer) => {
+                            let sent = peer
+                                .endpoints
+                                .iter()
+                                .map(|endpoint| {
+                                    let uuid = send_and_record(
+                                        &mut model,
+                                        &SendTarget::ToPeer(peer.clone(), endpoint.clone()),
+                                        &content,
+                                        self.pbat_enabled,
+                                        // the copies stay where they failed
+                                        DeliveryPolicy::Manual,
+                                        &mut self.sent_via,
+                                        &mut self.send_cursor,
+                                    );
+                                    (endpoint.clone(), uuid)
+                                })
+                                .collect();
+                            self.experiments.start(peer, &content, sent, current_time);
                         }
-                        PrepareSend::ToPeer(peer, endpoint) => {
-                            model.send_to_peer(
+                        None => {
+                            send_and_record(
+                                &mut model,
+                                &to_send,
                                 &content,
-                                &peer.uuid.clone(),
-                                peer.uuid.clone(),
-                                &endpoint,
                                 self.pbat_enabled,
+                                policy,
+                                &mut self.sent_via,
+                                &mut self.send_cursor,
                             );
                         }
                     }
@@ -185,23 +489,110 @@ impl MessagePromptView {
                 ),
             )
             .on_disabled_hover_text("The CP_PATH env variable must be set before starting the app");
+            ui.add_enabled(
+                matches!(current_mode, MessagingMode::Peer(Some(_))),
+                egui::Checkbox::new(&mut self.experiment_mode, " \u{1F9EA} Experiment"),
+            )
+            .on_hover_text("Send the same message over every endpoint of the peer and compare them")
+            .on_disabled_hover_text("Select a peer");
             ui.with_layout(
                 egui::Layout::right_to_left(egui::Align::Center),
-                |ui| match prepare_send {
-                    Some(to_send) => match to_send {
-                        PrepareSend::ToRoom(room) => {
-                            ui.colored_label(egui::Color32::GRAY, format!("to room {}", room.name));
-                        }
-                        PrepareSend::ToPeer(peer, endpoint) => {
-                            ui.colored_label(
-                                egui::Color32::GRAY,
-                                format!("to {} via {}", peer.name, endpoint.to_pretty_str()),
-                            );
-                        }
-                    },
-                    None => {}
+                |ui| match (&experiment_peer, &prepare_send) {
+                    (Some(peer), _) => {
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            format!(
+                                "to {} via its {} endpoints",
+                                peer.name,
+                                peer.endpoints.len()
+                            ),
+                        );
+                    }
+                    (None, Some(to_send)) => {
+                        ui.colored_label(egui::Color32::GRAY, format!("to {}", to_send.label()));
+                    }
+                    (None, None) => (),
                 },
             );
         });
+
+        if self.pbat_enabled {
+            if let (Some(plan), Some(target)) = (&data.contact_plan, &prepare_send) {
+                self.show_arrival_preview(ui, plan, target, data, current_time);
+            }
+        }
+    }
+
+    /// Earliest arrival along the contact plan when sending now, at the next contact or at
+    /// the time typed in the schedule menu. This is not the A-SABR prediction, which the
+    /// backend only computes when the message is sent.
+    fn show_arrival_preview(
+        &self,
+        ui: &mut Ui,
+        plan: &ContactPlan,
+        target: &SendTarget,
+        data: &MirroredData,
+        current_time: &DTChatTime,
+    ) {
+        if let SendTarget::ToPeer(_, endpoint) = target {
+            if endpoint.proto != EndpointProto::Bp {
+                ui.colored_label(
+                    egui::Color32::GRAY,
+                    format!(
+                        "\u{1F52D} No arrival estimate over {}: only bundles follow the contact plan",
+                        endpoint.proto.to_pretty_str()
+                    ),
+                );
+                return;
+            }
+        }
+        let mut options = vec![("Send now".to_string(), *current_time)];
+        if let Some(at) = next_contact(
+            plan,
+            target,
+            &data.local_peer,
+            &data.other_peers,
+            &data.room_participants,
+            current_time,
+        ) {
+            if at.timestamp_millis() > current_time.timestamp_millis() {
+                let label = format!(
+                    "At next contact ({})",
+                    at.ts_to_str(false, true, None, &chrono::Local)
+                );
+                options.push((label, at));
+            }
+        }
+        if let Some(at) = parse_local_time(&self.schedule_input, current_time) {
+            options.push((format!("At {}", self.schedule_input.trim()), at));
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.colored_label(
+                egui::Color32::GRAY,
+                "\u{1F52D} Contact plan estimate, not an A-SABR prediction:",
+            )
+            .on_hover_text(
+                "Earliest arrival following the contacts of the plan, ignoring their \
+                 capacity and queueing. The A-SABR prediction is only computed by the backend \
+                 when the message is sent.",
+            );
+            for (label, send_at) in options {
+                let Some(arrivals) = estimate_arrivals(
+                    plan,
+                    target,
+                    &data.local_peer,
+                    &data.other_peers,
+                    &data.room_participants,
+                    &send_at,
+                ) else {
+                    continue;
+                };
+                let (summary, details) = arrival_summary(&arrivals, &send_at);
+                ui.label(format!("{} \u{2192} {}", label, summary))
+                    .on_hover_text(details);
+                ui.separator();
+            }
+        });
     }
 }
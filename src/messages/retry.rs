@@ -0,0 +1,136 @@
+use std::collections::{HashMap, HashSet};
+
+use dtchat_backend::dtchat::Peer;
+use dtchat_backend::message::{ChatMessage, MessageStatus};
+use dtchat_backend::Endpoint;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::font::PrettyStr;
+
+/// What the user chose in the menu of a failed message
+#[derive(Clone)]
+pub enum RetryAction {
+    Retry(ChatMessage),
+    RetryVia(ChatMessage, Endpoint),
+    CopyToComposer(ChatMessage),
+}
+
+/// Menu of a failed message sent by the local peer, nothing for the other messages
+pub fn show_retry_menu(
+    ui: &mut egui::Ui,
+    msg: &ChatMessage,
+    local_peer: &Peer,
+    other_peers: &HashMap<String, Peer>,
+) -> Option<RetryAction> {
+    if msg.status != MessageStatus::Failed || msg.sender_uuid != local_peer.uuid {
+        return None;
+    }
+    let mut action = None;
+    if ui.button("\u{21BB} Retry").clicked() {
+        action = Some(RetryAction::Retry(msg.clone()));
+    }
+    // direct messages are sent to the peer as room
+    match other_peers.get(&msg.room_uuid) {
+        Some(peer) if !peer.endpoints.is_empty() => {
+            ui.menu_button("\u{21BB} Retry via another endpoint", |ui| {
+                for endpoint in &peer.endpoints {
+                    if ui.button(endpoint.to_pretty_str()).clicked() {
+                        action = Some(RetryAction::RetryVia(msg.clone(), endpoint.clone()));
+                    }
+                }
+            });
+        }
+        _ => {
+            ui.add_enabled(
+                false,
+                egui::Button::new("\u{21BB} Retry via another endpoint"),
+            )
+            .on_disabled_hover_text("Only for direct messages to a peer with endpoints");
+        }
+    }
+    if ui.button("\u{1F4CB} Copy to composer").clicked() {
+        action = Some(RetryAction::CopyToComposer(msg.clone()));
+    }
+    if action.is_some() {
+        ui.close_menu();
+    }
+    action
+}
+
+/// A retry and the message it sends again, recorded when the retry is handed to the model
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RetryLink {
+    pub retry: String,
+    pub original: String,
+}
+
+/// Which message is a retry of which, the model does not keep this link. Saved with the UI
+/// state.
+#[derive(Default)]
+pub struct RetryLinks {
+    // in sending order
+    links: Vec<RetryLink>,
+    // retry uuid -> original uuid
+    retry_of: HashMap<String, String>,
+}
+
+impl RetryLinks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn link(&mut self, retry: &str, original: &str) {
+        if self.retry_of.contains_key(retry) {
+            return;
+        }
+        self.retry_of
+            .insert(retry.to_string(), original.to_string());
+        self.links.push(RetryLink {
+            retry: retry.to_string(),
+            original: original.to_string(),
+        });
+    }
+
+    pub fn links(&self) -> &[RetryLink] {
+        &self.links
+    }
+
+    /// Replaces the links with the saved ones
+    pub fn restore(&mut self, links: &[RetryLink]) {
+        *self = Self::new();
+        for link in links {
+            self.link(&link.retry, &link.original);
+        }
+    }
+
+    /// Forgets the links whose retry or original message is no longer in the model
+    pub fn prune(&mut self, present: &HashSet<&str>) {
+        let in_model = |link: &RetryLink| {
+            present.contains(link.retry.as_str()) && present.contains(link.original.as_str())
+        };
+        if self.links.iter().all(in_model) {
+            return;
+        }
+        let kept: Vec<RetryLink> = self
+            .links
+            .iter()
+            .filter(|&link| in_model(link))
+            .cloned()
+            .collect();
+        self.restore(&kept);
+    }
+
+    pub fn original_of(&self, uuid: &str) -> Option<&str> {
+        self.retry_of.get(uuid).map(String::as_str)
+    }
+
+    /// In sending order
+    pub fn retries_of(&self, uuid: &str) -> Vec<&str> {
+        self.links
+            .iter()
+            .filter(|link| link.original == uuid)
+            .map(|link| link.retry.as_str())
+            .collect()
+    }
+}
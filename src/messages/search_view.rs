@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use dtchat_backend::message::{ChatMessage, Content};
+use eframe::egui;
+use egui::text::LayoutJob;
+use egui::{Color32, RichText, ScrollArea, TextFormat, Ui};
+
+use crate::main_view::MirroredData;
+
+// results listed at once, the others are only counted
+const MAX_RESULTS: usize = 100;
+// characters kept around the first match in the result list
+const CONTEXT_CHARS: usize = 30;
+
+fn peer_name(data: &MirroredData, uuid: &str) -> String {
+    if uuid == data.local_peer.uuid {
+        return data.local_peer.name.clone();
+    }
+    match data.other_peers.get(uuid) {
+        Some(peer) => peer.name.clone(),
+        None => "Unknown".to_string(),
+    }
+}
+
+/// Room name, or the peer name for a direct message
+fn conversation_name(data: &MirroredData, msg: &ChatMessage) -> String {
+    match data.rooms.get(&msg.room_uuid) {
+        Some(room) => format!("\u{1F465} {}", room.name),
+        None => format!("\u{1F464} {}", peer_name(data, &msg.room_uuid)),
+    }
+}
+
+fn file_name(msg: &ChatMessage) -> Option<String> {
+    match &msg.content {
+        Content::File(path) => Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string()),
+        Content::Text(_) => None,
+    }
+}
+
+/// Lowercased char by char, as `match_ranges` compares the texts
+fn lowercase(text: &str) -> String {
+    text.chars().flat_map(char::to_lowercase).collect()
+}
+
+/// Byte ranges of the case-insensitive matches of `query` in `text`. The lowercase form
+/// of a char may be longer than the char: the ranges are mapped back to the chars of
+/// `text` they were lowercased from.
+pub fn match_ranges(text: &str, query: &str) -> Vec<(usize, usize)> {
+    let query: Vec<char> = lowercase(query).chars().collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    // lowercase chars of the text, with the byte range of the char they come from
+    let lowered: Vec<(char, usize, usize)> = text
+        .char_indices()
+        .flat_map(|(start, c)| {
+            let end = start + c.len_utf8();
+            c.to_lowercase().map(move |lower| (lower, start, end))
+        })
+        .collect();
+    let mut ranges = Vec::new();
+    let mut idx = 0;
+    while idx + query.len() <= lowered.len() {
+        let window = &lowered[idx..idx + query.len()];
+        if window.iter().map(|(c, _, _)| *c).eq(query.iter().copied()) {
+            let start = window[0].1;
+            let end = window[query.len() - 1].2;
+            // a match starting inside the lowercase form of a char covers it
+            if ranges.last().is_none_or(|&(_, last_end)| start >= last_end) {
+                ranges.push((start, end));
+            }
+            idx += query.len();
+        } else {
+            idx += 1;
+        }
+    }
+    ranges
+}
+
+/// `text` with the matches of `query` highlighted
+pub fn highlighted(ui: &Ui, text: &str, query: &str, color: Color32) -> LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let normal = TextFormat {
+        font_id: font_id.clone(),
+        color,
+        ..Default::default()
+    };
+    let highlight = TextFormat {
+        font_id,
+        color: Color32::BLACK,
+        background: Color32::from_rgb(255, 210, 60),
+        ..Default::default()
+    };
+
+    let mut job = LayoutJob::default();
+    let mut last = 0;
+    for (start, end) in match_ranges(text, query) {
+        job.append(&text[last..start], 0.0, normal.clone());
+        job.append(&text[start..end], 0.0, highlight.clone());
+        last = end;
+    }
+    job.append(&text[last..], 0.0, normal);
+    job
+}
+
+/// Part of `text` around its first match
+fn snippet(text: &str, query: &str) -> String {
+    let text = text.replace('\n', " ");
+    let Some(&(start, end)) = match_ranges(&text, query).first() else {
+        return text.chars().take(2 * CONTEXT_CHARS).collect();
+    };
+    let before: Vec<char> = text[..start].chars().collect();
+    let after: Vec<char> = text[end..].chars().collect();
+    let mut snippet = String::new();
+    if before.len() > CONTEXT_CHARS {
+        snippet.push('\u{2026}');
+    }
+    snippet.extend(&before[before.len().saturating_sub(CONTEXT_CHARS)..]);
+    snippet.push_str(&text[start..end]);
+    snippet.extend(after.iter().take(CONTEXT_CHARS));
+    if after.len() > CONTEXT_CHARS {
+        snippet.push('\u{2026}');
+    }
+    snippet
+}
+
+/// What the search looks into, lowercased once per message
+struct SearchText {
+    content: String,
+    sender: String,
+    file: Option<String>,
+}
+
+impl SearchText {
+    fn new(msg: &ChatMessage, data: &MirroredData) -> Self {
+        Self {
+            content: lowercase(&msg.content_as_string()),
+            sender: lowercase(&peer_name(data, &msg.sender_uuid)),
+            file: file_name(msg).map(|name| lowercase(&name)),
+        }
+    }
+
+    fn file_matches(&self, query: &str) -> bool {
+        self.file.as_ref().is_some_and(|name| name.contains(query))
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        self.content.contains(query) || self.sender.contains(query) || self.file_matches(query)
+    }
+}
+
+/// Results of a query over the messages, kept until the query or the messages change
+struct SearchResults {
+    query: String,
+    message_count: usize,
+    // indices in the messages, latest first
+    indices: Vec<usize>,
+}
+
+/// Search bar over the messages of every room and peer
+pub struct MessageSearchView {
+    pub query: String,
+    // message uuid -> lowercased texts
+    lowered: HashMap<String, SearchText>,
+    results: Option<SearchResults>,
+}
+
+impl MessageSearchView {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            lowered: HashMap::new(),
+            results: None,
+        }
+    }
+
+    /// Searches the messages again when the query or the number of messages changed. The
+    /// model only appends messages.
+    fn update_results(&mut self, query: &str, data: &MirroredData) {
+        if self.results.as_ref().is_some_and(|results| {
+            results.query == query && results.message_count == data.messages.len()
+        }) {
+            return;
+        }
+        let lowered_query = lowercase(query);
+        let mut previous = std::mem::take(&mut self.lowered);
+        let mut indices = Vec::new();
+        for (idx, msg) in data.messages.iter().enumerate() {
+            let text = previous
+                .remove(&msg.uuid)
+                .unwrap_or_else(|| SearchText::new(msg, data));
+            if text.matches(&lowered_query) {
+                indices.push(idx);
+            }
+            self.lowered.insert(msg.uuid.clone(), text);
+        }
+        indices
+            .sort_by_key(|idx| std::cmp::Reverse(data.messages[*idx].send_time.timestamp_millis()));
+        self.results = Some(SearchResults {
+            query: query.to_string(),
+            message_count: data.messages.len(),
+            indices,
+        });
+    }
+
+    /// The query, when a search is running
+    pub fn active_query(&self) -> Option<&str> {
+        let query = self.query.trim();
+        (!query.is_empty()).then_some(query)
+    }
+
+    /// Returns the message clicked in the results
+    pub fn show(&mut self, ui: &mut Ui, data: &MirroredData) -> Option<ChatMessage> {
+        ui.horizontal(|ui| {
+            ui.label("\u{1F50D}");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("Search all messages, senders and files")
+                    .desired_width(300.0),
+            );
+            if !self.query.is_empty() && ui.button("\u{2716}").on_hover_text("Clear").clicked() {
+                self.query.clear();
+            }
+        });
+
+        let query = self.active_query()?.to_string();
+        self.update_results(&query, data);
+        let lowered_query = lowercase(&query);
+        let results: Vec<&ChatMessage> = match &self.results {
+            Some(results) => results
+                .indices
+                .iter()
+                .filter_map(|idx| data.messages.get(*idx))
+                .collect(),
+            None => Vec::new(),
+        };
+        let lowered = &self.lowered;
+
+        let mut clicked = None;
+        ui.colored_label(
+            Color32::GRAY,
+            format!("{} matching messages", results.len()),
+        );
+        ScrollArea::vertical()
+            .id_salt("search_results")
+            .max_height(180.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for msg in results.iter().take(MAX_RESULTS) {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(msg.send_time.ts_to_str(
+                                true,
+                                true,
+                                None,
+                                &chrono::Local,
+                            ))
+                            .color(Color32::GRAY),
+                        );
+                        ui.label(RichText::new(conversation_name(data, msg)).strong());
+                        let sender = peer_name(data, &msg.sender_uuid);
+                        ui.label(highlighted(
+                            ui,
+                            &format!("{}:", sender),
+                            &query,
+                            ui.visuals().text_color(),
+                        ));
+                        let file_matches = lowered
+                            .get(&msg.uuid)
+                            .is_some_and(|text| text.file_matches(&lowered_query));
+                        let text = match file_name(msg) {
+                            Some(name) if file_matches => format!("\u{1F4C1} {}", name),
+                            _ => snippet(&msg.content_as_string(), &query),
+                        };
+                        let response = ui
+                            .add(
+                                egui::Label::new(highlighted(
+                                    ui,
+                                    &text,
+                                    &query,
+                                    ui.visuals().text_color(),
+                                ))
+                                .sense(egui::Sense::click()),
+                            )
+                            .on_hover_text("Show in the conversation");
+                        if response.clicked() {
+                            clicked = Some((*msg).clone());
+                        }
+                    });
+                }
+                if results.len() > MAX_RESULTS {
+                    ui.colored_label(
+                        Color32::GRAY,
+                        format!("{} more, refine the search", results.len() - MAX_RESULTS),
+                    );
+                }
+            });
+        clicked
+    }
+}
+
+impl Default for MessageSearchView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_are_case_insensitive() {
+        assert_eq!(match_ranges("Hello hello", "HELLO"), [(0, 5), (6, 11)]);
+        assert!(match_ranges("Hello", "").is_empty());
+    }
+
+    #[test]
+    fn ranges_cover_the_chars_whose_lowercase_form_is_longer() {
+        // 'İ' (2 bytes) lowercases to "i\u{307}" (3 bytes)
+        let text = "İstanbul istanbul";
+        let ranges = match_ranges(text, "stan");
+        assert_eq!(ranges, [(2, 6), (11, 15)]);
+        assert_eq!(&text[ranges[0].0..ranges[0].1], "stan");
+        let ranges = match_ranges(text, "i\u{307}s");
+        assert_eq!(&text[ranges[0].0..ranges[0].1], "İs");
+    }
+}
@@ -0,0 +1,230 @@
+use dtchat_backend::dtchat::Peer;
+use dtchat_backend::message::{ChatMessage, MessageStatus};
+use dtchat_backend::time::DTChatTime;
+use egui::Color32;
+use egui_plot::{
+    uniform_grid_spacer, Arrows, AxisHints, GridMark, HLine, Legend, Line, LineStyle, MarkerShape,
+    Plot, PlotPoints, Points, VLine,
+};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use crate::utils::font::StatusDisplayHelper;
+
+/// Message sequence chart: one lifeline per peer, time flowing downwards.
+/// The y axis holds negated timestamps (ms) so that later events are drawn lower.
+pub struct MessageSequenceView {
+    pub auto_bounds: bool,
+    hovered: bool,
+}
+
+struct Lane {
+    uuid: String,
+    name: String,
+}
+
+impl MessageSequenceView {
+    pub fn new() -> Self {
+        Self {
+            auto_bounds: true,
+            hovered: false,
+        }
+    }
+
+    fn build_lanes(local_peer: &Peer, other_peers: &HashMap<String, Peer>) -> Vec<Lane> {
+        let mut others: Vec<&Peer> = other_peers.values().collect();
+        others.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut lanes = vec![Lane {
+            uuid: local_peer.uuid.clone(),
+            name: "Me".to_string(),
+        }];
+        lanes.extend(others.into_iter().map(|peer| Lane {
+            uuid: peer.uuid.clone(),
+            name: peer.name.clone(),
+        }));
+        lanes
+    }
+
+    /// Lanes receiving the message: the peer itself for a direct message, every other
+    /// peer for a room message we sent, and ourselves for a room message we received.
+    fn recipient_lanes(
+        message: &ChatMessage,
+        lanes: &[Lane],
+        local_peer: &Peer,
+        sender_lane: usize,
+    ) -> Vec<usize> {
+        if let Some(idx) = lanes.iter().position(|l| l.uuid == message.room_uuid) {
+            return vec![idx];
+        }
+        if message.sender_uuid == local_peer.uuid {
+            (0..lanes.len()).filter(|idx| *idx != sender_lane).collect()
+        } else {
+            vec![0]
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        messages: &[ChatMessage],
+        local_peer: &Peer,
+        other_peers: &HashMap<String, Peer>,
+        current_time: &DTChatTime,
+    ) {
+        let lanes = Self::build_lanes(local_peer, other_peers);
+        let lane_names: Vec<String> = lanes.iter().map(|l| l.name.clone()).collect();
+        let now = current_time.timestamp_millis() as f64;
+
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.auto_bounds, "Auto bounds");
+        });
+
+        // drag or scroll must cancel autobound (see MessageGraphView)
+        ui.input(|i| {
+            if self.hovered && (i.pointer.is_decidedly_dragging() || i.raw_scroll_delta.y != 0.0) {
+                self.auto_bounds = false;
+            }
+        });
+
+        let mut first_message = now;
+        let mut last_message = now;
+        for message in messages {
+            first_message = first_message.min(message.send_time.timestamp_millis() as f64);
+            if let Some(pred) = message.predicted_arrival_time {
+                last_message = last_message.max(pred.timestamp_millis() as f64);
+            }
+        }
+
+        let x_axes = vec![AxisHints::new_x()
+            .formatter(move |x: GridMark, _range: &RangeInclusive<f64>| {
+                if x.value < 0.0 || x.value.fract() != 0.0 {
+                    return String::new();
+                }
+                lane_names
+                    .get(x.value as usize)
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .placement(egui_plot::VPlacement::Top)];
+
+        let y_axes =
+            vec![
+                AxisHints::new_y().formatter(|y: GridMark, _range: &RangeInclusive<f64>| {
+                    match DTChatTime::from_timestamp_millis(-y.value as i64) {
+                        Some(time) => time.ts_to_str(false, true, None, &chrono::Local),
+                        None => String::new(),
+                    }
+                }),
+            ];
+
+        let plot_height = ui.available_height().max(300.0);
+
+        let plt = Plot::new("DTChat Sequence")
+            .allow_zoom(true)
+            .allow_drag(true)
+            .legend(Legend::default().position(egui_plot::Corner::LeftBottom))
+            .show_x(false)
+            .show_y(true)
+            .include_x(-0.5)
+            .include_x(lanes.len() as f64 - 0.5)
+            .include_y(-first_message)
+            .include_y(-(last_message + (last_message - first_message) * 0.1))
+            .custom_x_axes(x_axes)
+            .custom_y_axes(y_axes)
+            .x_grid_spacer(uniform_grid_spacer(|_| [1.0, 5.0, 10.0]))
+            .label_formatter(|name, value| {
+                let time = match DTChatTime::from_timestamp_millis(-value.y as i64) {
+                    Some(time) => time.ts_to_str(false, true, None, &chrono::Local),
+                    None => String::new(),
+                };
+                if !name.is_empty() {
+                    format!("{}\n{}", name, time)
+                } else {
+                    time
+                }
+            })
+            .height(plot_height)
+            .show(ui, |plot_ui| {
+                plot_ui.set_auto_bounds(self.auto_bounds);
+
+                for idx in 0..lanes.len() {
+                    plot_ui.vline(VLine::new(idx as f64).color(Color32::DARK_GRAY).width(1.5));
+                }
+                plot_ui.hline(HLine::new(-now).color(Color32::ORANGE).name("Current Time"));
+
+                for message in messages {
+                    let Some(from) = lanes.iter().position(|l| l.uuid == message.sender_uuid)
+                    else {
+                        continue;
+                    };
+                    let name = message.status.get_icon_text(&lanes[from].name);
+                    let color = message.status.get_color();
+                    let send = -(message.send_time.timestamp_millis() as f64);
+
+                    if message.status == MessageStatus::Failed {
+                        plot_ui.points(
+                            Points::new(vec![[from as f64, send]])
+                                .shape(MarkerShape::Cross)
+                                .radius(6.0)
+                                .color(color)
+                                .name(&name),
+                        );
+                        continue;
+                    }
+
+                    for to in Self::recipient_lanes(message, &lanes, local_peer, from) {
+                        match (message.receive_time, message.predicted_arrival_time) {
+                            (Some(recv), _) => {
+                                let recv = -(recv.timestamp_millis() as f64);
+                                plot_ui.arrows(
+                                    Arrows::new(vec![[from as f64, send]], vec![[to as f64, recv]])
+                                        .color(color)
+                                        .tip_length(10.0)
+                                        .name(&name),
+                                );
+                            }
+                            // Still in flight: ghost arrow towards the predicted arrival
+                            (None, Some(pred)) => {
+                                let pred = -(pred.timestamp_millis() as f64);
+                                plot_ui.line(
+                                    Line::new(PlotPoints::from(vec![
+                                        [from as f64, send],
+                                        [to as f64, pred],
+                                    ]))
+                                    .color(color)
+                                    .style(LineStyle::dashed_dense())
+                                    .name(&name),
+                                );
+                                plot_ui.points(
+                                    Points::new(vec![[to as f64, pred]])
+                                        .shape(MarkerShape::Circle)
+                                        .filled(false)
+                                        .radius(4.0)
+                                        .color(color)
+                                        .name(&name),
+                                );
+                            }
+                            (None, None) => {
+                                plot_ui.points(
+                                    Points::new(vec![[from as f64, send]])
+                                        .shape(MarkerShape::Diamond)
+                                        .radius(4.0)
+                                        .color(color)
+                                        .name(&name),
+                                );
+                            }
+                        }
+                    }
+                }
+            });
+        self.hovered = plt.response.hovered();
+    }
+}
+
+impl Default for MessageSequenceView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
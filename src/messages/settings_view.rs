@@ -1,14 +1,42 @@
 use std::collections::HashMap;
 
-use crate::messages::{MessageCountToDisplay, MessageViewType, ProtoFilter};
+use crate::messages::{
+    MessageCountToDisplay, MessageViewType, ProtoFilter, StatusFilter, TimeFilter,
+};
 use crate::utils::font::PrettyStr;
+use chrono::{Local, NaiveDateTime, NaiveTime, TimeZone};
 use dtchat_backend::dtchat::Peer;
 use dtchat_backend::message::SortStrategy;
 use dtchat_backend::EndpointProto;
 use egui::{ComboBox, Slider, Ui};
 
+const RELATIVE_TIME_FILTERS: [i64; 6] = [5, 15, 30, 60, 6 * 60, 24 * 60];
+
 pub struct MessageSettingsView {
     last_sort_strategy_peer: Option<Peer>,
+    // bounds of the time range being typed
+    range_from: String,
+    range_to: String,
+}
+
+/// "2025-06-01 14:30", "2025-06-01 14:30:15" or "14:30" for today, local time, in ms
+fn parse_local_datetime(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let datetime = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| {
+            let time = NaiveTime::parse_from_str(text, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(text, "%H:%M"))
+                .ok()?;
+            Some(Local::now().date_naive().and_time(time))
+        })?;
+    Some(
+        Local
+            .from_local_datetime(&datetime)
+            .earliest()?
+            .timestamp_millis(),
+    )
 }
 
 fn get_str_for_strat(local_peer_uuid: String, _peer: Option<Peer>, strat: &SortStrategy) -> String {
@@ -46,6 +74,72 @@ impl MessageSettingsView {
     pub fn new() -> Self {
         Self {
             last_sort_strategy_peer: None,
+            range_from: String::new(),
+            range_to: String::new(),
+        }
+    }
+
+    /// Status and time range filters of the current context
+    pub fn show_filters(
+        &mut self,
+        ui: &mut Ui,
+        status_filter: &mut StatusFilter,
+        time_filter: &mut TimeFilter,
+        request_filter: &mut bool,
+    ) {
+        let previous_status = status_filter.clone();
+        ComboBox::from_id_salt("status_filter")
+            .selected_text(status_filter.to_pretty_str())
+            .show_ui(ui, |ui| {
+                for opt in StatusFilter::ALL {
+                    let text = opt.to_pretty_str();
+                    ui.selectable_value(status_filter, opt, text);
+                }
+            });
+        if previous_status != *status_filter {
+            *request_filter = true;
+        }
+
+        let previous_time = *time_filter;
+        ui.menu_button(format!("\u{1F552} {}", time_filter.to_pretty_str()), |ui| {
+            ui.selectable_value(time_filter, TimeFilter::NoFilter, "All time");
+            for minutes in RELATIVE_TIME_FILTERS {
+                let opt = TimeFilter::LastMinutes(minutes);
+                ui.selectable_value(time_filter, opt, opt.to_pretty_str());
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("From");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.range_from)
+                        .hint_text("YYYY-MM-DD HH:MM")
+                        .desired_width(120.0),
+                );
+                ui.label("to");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.range_to)
+                        .hint_text("YYYY-MM-DD HH:MM")
+                        .desired_width(120.0),
+                );
+                let range = parse_local_datetime(&self.range_from)
+                    .zip(parse_local_datetime(&self.range_to))
+                    .filter(|(from, to)| from <= to);
+                if ui
+                    .add_enabled(range.is_some(), egui::Button::new("Apply"))
+                    .on_disabled_hover_text("Local times, \"HH:MM\" is for today")
+                    .clicked()
+                {
+                    if let Some((from, to)) = range {
+                        *time_filter = TimeFilter::Between(from, to);
+                    }
+                }
+            });
+            if previous_time != *time_filter {
+                ui.close_menu();
+            }
+        });
+        if previous_time != *time_filter {
+            *request_filter = true;
         }
     }
 
@@ -69,13 +163,18 @@ impl MessageSettingsView {
                     .show_ui(ui, |ui| {
                         ui.selectable_value(
                             current_view,
-                            MessageViewType::MessageGraph,
-                            MessageViewType::MessageGraph.name(),
+                            MessageViewType::Graph,
+                            MessageViewType::Graph.name(),
+                        );
+                        ui.selectable_value(
+                            current_view,
+                            MessageViewType::List,
+                            MessageViewType::List.name(),
                         );
                         ui.selectable_value(
                             current_view,
-                            MessageViewType::MessageList,
-                            MessageViewType::MessageList.name(),
+                            MessageViewType::Sequence,
+                            MessageViewType::Sequence.name(),
                         );
                 });
 
@@ -1,13 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use dtchat_backend::dtchat::{Peer, Room};
-use egui::Ui;
+use egui::{RichText, Ui};
 
+use crate::messages::smart_view::{SmartView, SmartViewAction};
 use crate::messages::{MessagingMode, PreferencesContext};
 
 pub struct SideSelectionView {
     last_peer: Option<Peer>,
     last_room: Option<Room>,
+    pub smart_views: Vec<SmartView>,
+    // index of the smart view applied last, until another conversation is selected
+    pub active_smart_view: Option<usize>,
+    // handled by the messages view, which owns the preferences and the search
+    pub smart_view_action: Option<SmartViewAction>,
+    new_view_name: String,
+    // conversation uuid -> unread messages, set by the messages view
+    pub unread: HashMap<String, usize>,
+    // peers and rooms without toasts
+    pub muted: BTreeSet<String>,
+}
+
+/// "name", or "name (3)" in bold with unread messages
+fn label_with_unread(text: &str, unread: usize) -> RichText {
+    if unread == 0 {
+        RichText::new(text)
+    } else {
+        RichText::new(format!("{} ({})", text, unread)).strong()
+    }
 }
 
 impl SideSelectionView {
@@ -15,6 +35,89 @@ impl SideSelectionView {
         Self {
             last_room: None,
             last_peer: None,
+            smart_views: Vec::new(),
+            active_smart_view: None,
+            smart_view_action: None,
+            new_view_name: String::new(),
+            unread: HashMap::new(),
+            muted: BTreeSet::new(),
+        }
+    }
+
+    /// Label of a peer or room entry, with its unread count and whether it is muted
+    fn entry_label(&self, text: String, uuid: &str) -> RichText {
+        let text = if self.muted.contains(uuid) {
+            format!("{} \u{1F515}", text)
+        } else {
+            text
+        };
+        label_with_unread(&text, self.unread.get(uuid).copied().unwrap_or(0))
+    }
+
+    fn show_mute_menu(&mut self, response: &egui::Response, uuid: &str) {
+        response.context_menu(|ui| {
+            if self.muted.contains(uuid) {
+                if ui.button("\u{1F514} Unmute notifications").clicked() {
+                    self.muted.remove(uuid);
+                    ui.close_menu();
+                }
+            } else if ui.button("\u{1F515} Mute notifications").clicked() {
+                self.muted.insert(uuid.to_string());
+                ui.close_menu();
+            }
+        });
+    }
+
+    fn show_smart_views(&mut self, ui: &mut Ui) {
+        egui::CollapsingHeader::new(format!("\u{2605} Views ({})", self.smart_views.len()))
+            .id_salt("smart_views")
+            .default_open(true)
+            .show(ui, |ui| {
+                for (idx, view) in self.smart_views.iter().enumerate() {
+                    let response = ui
+                        .selectable_label(self.active_smart_view == Some(idx), &view.name)
+                        .on_hover_text("Right click to delete");
+                    if response.clicked() {
+                        self.smart_view_action = Some(SmartViewAction::Apply(idx));
+                    }
+                    response.context_menu(|ui| {
+                        if ui.button("\u{2716} Delete").clicked() {
+                            self.smart_view_action = Some(SmartViewAction::Delete(idx));
+                            ui.close_menu();
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.new_view_name)
+                            .hint_text("Name")
+                            .desired_width(60.0),
+                    );
+                    let name = self.new_view_name.trim().to_string();
+                    if ui
+                        .add_enabled(!name.is_empty(), egui::Button::new("Save"))
+                        .on_hover_text(
+                            "Save the current conversation, filters, sort and search as a view",
+                        )
+                        .clicked()
+                    {
+                        self.smart_view_action = Some(SmartViewAction::SaveCurrent(name));
+                        self.new_view_name.clear();
+                    }
+                });
+            });
+    }
+
+    pub fn remembered(&self) -> (Option<&Peer>, Option<&Room>) {
+        (self.last_peer.as_ref(), self.last_room.as_ref())
+    }
+
+    /// Keeps the peer/room selected from outside, e.g. from a search result
+    pub fn remember(&mut self, mode: &MessagingMode) {
+        match mode {
+            MessagingMode::Peer(Some(peer)) => self.last_peer = Some(peer.clone()),
+            MessagingMode::Room(Some(room)) => self.last_room = Some(room.clone()),
+            _ => (),
         }
     }
 
@@ -27,6 +130,12 @@ impl SideSelectionView {
         current_mode: &mut MessagingMode,
         request_filter: &mut bool,
     ) {
+        let unread_in = |uuids: &mut dyn Iterator<Item = &String>| -> usize {
+            uuids.filter_map(|uuid| self.unread.get(uuid)).sum()
+        };
+        let peers_unread = unread_in(&mut peers.keys());
+        let rooms_unread = unread_in(&mut rooms.keys());
+
         ui.horizontal(|ui| {
             ui.spacing_mut().item_spacing.x = 1.0;
             if ui
@@ -41,7 +150,7 @@ impl SideSelectionView {
                 .selectable_value(
                     current_mode,
                     MessagingMode::Peer(self.last_peer.clone()),
-                    "Peers",
+                    label_with_unread("Peers", peers_unread),
                 )
                 .clicked()
             {
@@ -62,7 +171,7 @@ impl SideSelectionView {
                 .selectable_value(
                     current_mode,
                     MessagingMode::Room(self.last_room.clone()),
-                    "Rooms",
+                    label_with_unread("Rooms", rooms_unread),
                 )
                 .clicked()
             {
@@ -79,24 +188,23 @@ impl SideSelectionView {
             };
         });
         ui.separator();
+        self.show_smart_views(ui);
+        ui.separator();
         egui::ScrollArea::vertical().show(ui, |ui| match current_mode {
             MessagingMode::Peer(peer_opt) => {
                 if peers.is_empty() {
                     ui.label("No peers");
                 } else {
                     for (_peer_uuid, peer) in peers {
-                        if ui
-                            .selectable_value(
-                                peer_opt,
-                                Some(peer.clone()),
-                                format!("\u{1F464} {}", &peer.name),
-                            )
-                            .clicked()
-                        {
+                        let label =
+                            self.entry_label(format!("\u{1F464} {}", &peer.name), &peer.uuid);
+                        let response = ui.selectable_value(peer_opt, Some(peer.clone()), label);
+                        if response.clicked() {
                             self.last_peer = Some(peer.clone());
                             pref_ctx.load_context(&peer.uuid);
                             *request_filter = true;
                         };
+                        self.show_mute_menu(&response, &peer.uuid);
                     }
                 }
             }
@@ -105,18 +213,15 @@ impl SideSelectionView {
                     ui.label("No rooms");
                 } else {
                     for (_room_uuid, room) in rooms {
-                        if ui
-                            .selectable_value(
-                                room_opt,
-                                Some(room.clone()),
-                                format!("\u{1F465} {}", &room.name),
-                            )
-                            .clicked()
-                        {
+                        let label =
+                            self.entry_label(format!("\u{1F465} {}", &room.name), &room.uuid);
+                        let response = ui.selectable_value(room_opt, Some(room.clone()), label);
+                        if response.clicked() {
                             self.last_room = Some(room.clone());
                             pref_ctx.load_context(&room.uuid);
                             *request_filter = true;
                         };
+                        self.show_mute_menu(&response, &room.uuid);
                     }
                 }
             }
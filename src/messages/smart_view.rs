@@ -0,0 +1,126 @@
+use dtchat_backend::message::SortStrategy;
+use serde::{Deserialize, Serialize};
+
+use crate::main_view::MirroredData;
+use crate::messages::{no_time_filter, MessagingMode, ProtoFilter, StatusFilter, TimeFilter};
+
+/// Conversation of a smart view, by uuid
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SavedMode {
+    All,
+    Peer(String),
+    Room(String),
+}
+
+impl From<&MessagingMode> for SavedMode {
+    fn from(mode: &MessagingMode) -> Self {
+        match mode {
+            MessagingMode::Peer(Some(peer)) => SavedMode::Peer(peer.uuid.clone()),
+            MessagingMode::Room(Some(room)) => SavedMode::Room(room.uuid.clone()),
+            _ => SavedMode::All,
+        }
+    }
+}
+
+impl SavedMode {
+    /// None when the peer or room is not known (anymore)
+    pub fn resolve(&self, data: &MirroredData) -> Option<MessagingMode> {
+        match self {
+            SavedMode::Peer(uuid) => Some(MessagingMode::Peer(Some(
+                data.other_peers.get(uuid)?.clone(),
+            ))),
+            SavedMode::Room(uuid) => Some(MessagingMode::Room(Some(data.rooms.get(uuid)?.clone()))),
+            SavedMode::All => Some(MessagingMode::All),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SavedSort {
+    Standard,
+    Relative(String),
+}
+
+impl From<&SortStrategy> for SavedSort {
+    fn from(strategy: &SortStrategy) -> Self {
+        match strategy {
+            SortStrategy::Standard => SavedSort::Standard,
+            SortStrategy::Relative(uuid) => SavedSort::Relative(uuid.clone()),
+        }
+    }
+}
+
+impl SavedSort {
+    pub fn strategy(&self) -> SortStrategy {
+        match self {
+            SavedSort::Standard => SortStrategy::Standard,
+            SavedSort::Relative(uuid) => SortStrategy::Relative(uuid.clone()),
+        }
+    }
+}
+
+/// Named combination of conversation, filters, sort strategy and search query
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SmartView {
+    pub name: String,
+    pub mode: SavedMode,
+    // "udp", "tcp" or "bp", all protocols when missing
+    #[serde(default)]
+    pub protocol: Option<String>,
+    // see `StatusFilter::name`
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default = "no_time_filter")]
+    pub time_filter: TimeFilter,
+    pub sort: SavedSort,
+    #[serde(default)]
+    pub search: String,
+}
+
+impl SmartView {
+    pub fn new(
+        name: String,
+        mode: &MessagingMode,
+        protocol_filter: &ProtoFilter,
+        status_filter: &StatusFilter,
+        time_filter: &TimeFilter,
+        sort_strategy: &SortStrategy,
+        search: &str,
+    ) -> Self {
+        Self {
+            name,
+            mode: SavedMode::from(mode),
+            protocol: protocol_filter.saved_name(),
+            status: status_filter.saved_name(),
+            time_filter: *time_filter,
+            sort: SavedSort::from(sort_strategy),
+            search: search.to_string(),
+        }
+    }
+
+    /// The peer or room of the view, all messages when it is not known anymore
+    pub fn messaging_mode(&self, data: &MirroredData) -> MessagingMode {
+        self.mode.resolve(data).unwrap_or(MessagingMode::All)
+    }
+
+    pub fn protocol_filter(&self) -> ProtoFilter {
+        ProtoFilter::from_saved_name(self.protocol.as_deref())
+    }
+
+    pub fn status_filter(&self) -> StatusFilter {
+        StatusFilter::from_saved_name(self.status.as_deref())
+    }
+
+    pub fn sort_strategy(&self) -> SortStrategy {
+        self.sort.strategy()
+    }
+}
+
+/// What the user did in the smart view list of the side panel
+pub enum SmartViewAction {
+    Apply(usize),
+    SaveCurrent(String),
+    Delete(usize),
+}
@@ -0,0 +1,93 @@
+use std::collections::{BTreeMap, HashMap};
+
+use dtchat_backend::message::ChatMessage;
+
+use crate::main_view::MirroredData;
+
+/// Room uuid, or the uuid of the other peer for a direct message
+pub fn conversation_uuid(msg: &ChatMessage, data: &MirroredData) -> String {
+    if data.rooms.contains_key(&msg.room_uuid) || msg.sender_uuid == data.local_peer.uuid {
+        msg.room_uuid.clone()
+    } else {
+        msg.sender_uuid.clone()
+    }
+}
+
+/// When the message reached the local peer, ms
+pub fn arrival_ms(msg: &ChatMessage) -> i64 {
+    msg.receive_time.unwrap_or(msg.send_time).timestamp_millis()
+}
+
+/// Last-read marker per conversation and the unread counts derived from it
+pub struct UnreadTracker {
+    // conversation uuid -> arrival time of the last message read
+    pub last_read: BTreeMap<String, i64>,
+    // marker of the conversations never opened
+    pub since_ms: i64,
+    counts: HashMap<String, usize>,
+    // conversation being viewed and its marker when it was opened
+    viewing: Option<(String, i64)>,
+}
+
+impl UnreadTracker {
+    pub fn new(since_ms: i64) -> Self {
+        Self {
+            last_read: BTreeMap::new(),
+            since_ms,
+            counts: HashMap::new(),
+            viewing: None,
+        }
+    }
+
+    /// Forgets the markers of the peers and rooms no longer known
+    pub fn prune(&mut self, data: &MirroredData) {
+        self.last_read
+            .retain(|uuid, _| data.other_peers.contains_key(uuid) || data.rooms.contains_key(uuid));
+    }
+
+    fn marker(&self, uuid: &str) -> i64 {
+        self.last_read.get(uuid).copied().unwrap_or(self.since_ms)
+    }
+
+    /// Counts the messages received after the markers, the conversation being viewed is
+    /// marked as read
+    pub fn update(&mut self, data: &MirroredData, viewing: Option<&str>) {
+        match (viewing, &self.viewing) {
+            (Some(uuid), Some((viewed, _))) if viewed == uuid => (),
+            (Some(uuid), _) => self.viewing = Some((uuid.to_string(), self.marker(uuid))),
+            (None, _) => self.viewing = None,
+        }
+
+        self.counts.clear();
+        let mut latest: HashMap<String, i64> = HashMap::new();
+        for msg in &data.messages {
+            if msg.sender_uuid == data.local_peer.uuid {
+                continue;
+            }
+            let uuid = conversation_uuid(msg, data);
+            let arrival = arrival_ms(msg);
+            if arrival > self.marker(&uuid) {
+                *self.counts.entry(uuid.clone()).or_insert(0) += 1;
+            }
+            let last = latest.entry(uuid).or_insert(arrival);
+            *last = (*last).max(arrival);
+        }
+
+        if let Some((uuid, _)) = &self.viewing {
+            self.counts.remove(uuid);
+            if let Some(last) = latest.get(uuid) {
+                let marker = self.marker(uuid).max(*last);
+                self.last_read.insert(uuid.clone(), marker);
+            }
+        }
+    }
+
+    pub fn counts(&self) -> &HashMap<String, usize> {
+        &self.counts
+    }
+
+    /// Marker of the conversation being viewed, as it was when the conversation was opened
+    pub fn divider_ms(&self) -> Option<i64> {
+        self.viewing.as_ref().map(|(_, marker)| *marker)
+    }
+}
@@ -1,44 +1,297 @@
-use crate::{app::DisplayEvent, utils::font::PrettyStr};
-use eframe::egui::{self, ScrollArea};
-use std::collections::VecDeque;
-
-pub struct NetworkView;
-
-fn show_events_in_columns(ui: &mut egui::Ui, heading: &str, app_events: &VecDeque<DisplayEvent>) {
-    ui.push_id(format!("{}_section", heading), |ui| {
-        ui.group(|ui| {
-            ui.vertical(|ui| {
-                ui.heading(heading);
-                ui.separator();
-
-                ScrollArea::vertical()
-                    .id_salt(heading)
-                    .auto_shrink([false; 2])
-                    .stick_to_bottom(true)
-                    .show(ui, |ui| {
-                        if app_events.is_empty() {
-                            ui.label("No events");
-                        } else {
-                            // Show newest events first
-                            for (index, event) in app_events.iter().enumerate() {
-                                ui.push_id(format!("{}_{}", heading, index), |ui| {
-                                    ui.colored_label(event.get_color(), event.to_pretty_str());
-                                });
-                            }
-                        }
+use crate::{
+    app::{DisplayEvent, EventLevel},
+    messages::ProtoFilter,
+    utils::{
+        font::PrettyStr,
+        journal::{journal_dir, JournalReplay},
+    },
+};
+use dtchat_backend::{dtchat::Peer, EndpointProto};
+use eframe::egui::{self, Color32, ComboBox, RichText, ScrollArea, TextEdit};
+use egui_file_dialog::FileDialog;
+use std::collections::{HashMap, VecDeque};
+
+pub struct NetworkView {
+    level_filter: Option<EventLevel>,
+    protocol_filter: ProtoFilter,
+    // peer uuid
+    peer_filter: Option<String>,
+    endpoint_filter: String,
+    text_filter: String,
+    auto_scroll: bool,
+    // past session opened from a journal, shown instead of the live events
+    replay: Option<JournalReplay>,
+    replay_error: Option<String>,
+    file_dialog: FileDialog,
+}
+
+fn event_details(event: &DisplayEvent, peer_names: &HashMap<String, String>) -> String {
+    let mut details = vec![format!("level: {}", event.level.name())];
+    if let Some(peer_uuid) = &event.peer_uuid {
+        let name = peer_names.get(peer_uuid).unwrap_or(peer_uuid);
+        details.push(format!("peer: {}", name));
+    }
+    if let Some(endpoint) = &event.endpoint {
+        details.push(format!("endpoint: {}", endpoint));
+    }
+    if let Some(protocol) = &event.protocol {
+        details.push(format!("protocol: {}", protocol.to_pretty_str()));
+    }
+    if let Some(bytes) = event.bytes {
+        details.push(format!("bytes: {}", bytes));
+    }
+    if event.message_uuid.is_some() {
+        details.push("Click to show the message".to_string());
+    }
+    details.join("\n")
+}
+
+impl NetworkView {
+    pub fn new() -> Self {
+        Self {
+            level_filter: None,
+            protocol_filter: ProtoFilter::NoFilter,
+            peer_filter: None,
+            endpoint_filter: String::new(),
+            text_filter: String::new(),
+            auto_scroll: true,
+            replay: None,
+            replay_error: None,
+            file_dialog: FileDialog::new().initial_directory(journal_dir()),
+        }
+    }
+
+    fn matches(&self, event: &DisplayEvent) -> bool {
+        if let Some(level) = self.level_filter {
+            if event.level != level {
+                return false;
+            }
+        }
+        if let ProtoFilter::Filter(proto) = &self.protocol_filter {
+            if event.protocol.as_ref() != Some(proto) {
+                return false;
+            }
+        }
+        if self.peer_filter.is_some() && event.peer_uuid != self.peer_filter {
+            return false;
+        }
+        if !self.endpoint_filter.is_empty() {
+            match &event.endpoint {
+                Some(endpoint) => {
+                    if !endpoint
+                        .to_lowercase()
+                        .contains(&self.endpoint_filter.to_lowercase())
+                    {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+        if !self.text_filter.is_empty()
+            && !event
+                .message
+                .to_lowercase()
+                .contains(&self.text_filter.to_lowercase())
+        {
+            return false;
+        }
+        true
+    }
+
+    fn show_replay_bar(&mut self, ui: &mut egui::Ui) {
+        self.file_dialog.update(ui.ctx());
+        if let Some(path) = self.file_dialog.take_picked() {
+            match JournalReplay::load(&path) {
+                Ok(replay) => {
+                    self.replay = Some(replay);
+                    self.replay_error = None;
+                }
+                Err(err) => self.replay_error = Some(err),
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if ui
+                .button("\u{1F4C2} Open journal")
+                .on_hover_text("Show the events of a past session")
+                .clicked()
+            {
+                self.file_dialog.pick_file();
+            }
+            if let Some(replay) = &self.replay {
+                ui.colored_label(
+                    Color32::YELLOW,
+                    format!("Replaying {}", replay.path.display()),
+                );
+                if replay.skipped_lines > 0 {
+                    ui.colored_label(
+                        Color32::ORANGE,
+                        format!("{} unreadable lines skipped", replay.skipped_lines),
+                    )
+                    .on_hover_text(replay.first_error.clone().unwrap_or_default());
+                }
+                if ui.button("Back to live").clicked() {
+                    self.replay = None;
+                }
+            }
+            if let Some(err) = &self.replay_error {
+                ui.colored_label(Color32::RED, err);
+            }
+        });
+    }
+
+    fn show_filters(&mut self, ui: &mut egui::Ui, peer_names: &HashMap<String, String>) {
+        ui.horizontal(|ui| {
+            ui.label("Level:");
+            ComboBox::from_id_salt("event_level_filter")
+                .selected_text(self.level_filter.map_or("All", |level| level.name()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.level_filter, None, "All");
+                    for level in [EventLevel::Info, EventLevel::Debug, EventLevel::Error] {
+                        ui.selectable_value(&mut self.level_filter, Some(level), level.name());
+                    }
+                });
+
+            ui.separator();
+            ui.label("Protocol:");
+            ComboBox::from_id_salt("event_protocol_filter")
+                .selected_text(self.protocol_filter.to_pretty_str())
+                .show_ui(ui, |ui| {
+                    for opt in [
+                        ProtoFilter::NoFilter,
+                        ProtoFilter::Filter(EndpointProto::Tcp),
+                        ProtoFilter::Filter(EndpointProto::Udp),
+                        ProtoFilter::Filter(EndpointProto::Bp),
+                    ] {
+                        let text = opt.to_pretty_str();
+                        ui.selectable_value(&mut self.protocol_filter, opt, text);
+                    }
+                });
+
+            ui.separator();
+            ui.label("Peer:");
+            let selected_peer = match &self.peer_filter {
+                Some(uuid) => peer_names.get(uuid).unwrap_or(uuid).clone(),
+                None => "All".to_string(),
+            };
+            ComboBox::from_id_salt("event_peer_filter")
+                .selected_text(selected_peer)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.peer_filter, None, "All");
+                    let mut peers: Vec<_> = peer_names.iter().collect();
+                    peers.sort_by(|a, b| a.1.cmp(b.1));
+                    for (uuid, name) in peers {
+                        ui.selectable_value(&mut self.peer_filter, Some(uuid.clone()), name);
+                    }
+                });
+
+            ui.separator();
+            ui.label("Endpoint:");
+            ui.add(TextEdit::singleline(&mut self.endpoint_filter).desired_width(120.0));
+
+            ui.separator();
+            ui.label("\u{1F50D}");
+            ui.add(
+                TextEdit::singleline(&mut self.text_filter)
+                    .hint_text("Search events")
+                    .desired_width(160.0),
+            );
+
+            ui.separator();
+            let scroll_text = if self.auto_scroll {
+                "\u{23F8} Pause"
+            } else {
+                "\u{25B6} Resume"
+            };
+            if ui
+                .button(scroll_text)
+                .on_hover_text("Pause or resume the auto-scroll")
+                .clicked()
+            {
+                self.auto_scroll = !self.auto_scroll;
+            }
+        });
+    }
+
+    /// Returns the uuid of the message linked to the clicked event, if any
+    fn show_events_in_columns(
+        &self,
+        ui: &mut egui::Ui,
+        heading: &str,
+        events: &VecDeque<DisplayEvent>,
+        peer_names: &HashMap<String, String>,
+    ) -> Option<String> {
+        let mut clicked = None;
+        let filtered: Vec<&DisplayEvent> = events.iter().filter(|e| self.matches(e)).collect();
+        ui.push_id(format!("{}_section", heading), |ui| {
+            ui.group(|ui| {
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.heading(heading);
+                        ui.label(format!("{}/{}", filtered.len(), events.len()));
                     });
+                    ui.separator();
+
+                    ScrollArea::vertical()
+                        .id_salt(heading)
+                        .auto_shrink([false; 2])
+                        .stick_to_bottom(self.auto_scroll)
+                        .show(ui, |ui| {
+                            if filtered.is_empty() {
+                                ui.label("No events");
+                            } else {
+                                for (index, event) in filtered.iter().enumerate() {
+                                    ui.push_id(format!("{}_{}", heading, index), |ui| {
+                                        let mut text = RichText::new(event.to_pretty_str())
+                                            .color(event.get_color());
+                                        let sense = match &event.message_uuid {
+                                            Some(_) => {
+                                                text = text.underline();
+                                                egui::Sense::click()
+                                            }
+                                            None => egui::Sense::hover(),
+                                        };
+                                        let response = ui
+                                            .add(egui::Label::new(text).sense(sense))
+                                            .on_hover_text(event_details(event, peer_names));
+                                        if response.clicked() {
+                                            clicked = event.message_uuid.clone();
+                                        }
+                                    });
+                                }
+                            }
+                        });
+                });
             });
         });
-    });
-}
+        clicked
+    }
 
-impl NetworkView {
     pub fn show(
         &mut self,
         ui: &mut egui::Ui,
         network_events: &VecDeque<DisplayEvent>,
         app_events: &VecDeque<DisplayEvent>,
-    ) {
+        local_peer: &Peer,
+        other_peers: &HashMap<String, Peer>,
+    ) -> Option<String> {
+        let mut peer_names: HashMap<String, String> = other_peers
+            .iter()
+            .map(|(uuid, peer)| (uuid.clone(), peer.name.clone()))
+            .collect();
+        peer_names.insert(local_peer.uuid.clone(), format!("{} (me)", local_peer.name));
+
+        self.show_replay_bar(ui);
+        self.show_filters(ui, &peer_names);
+        ui.separator();
+
+        let (network_events, app_events) = match &self.replay {
+            Some(replay) => (&replay.network_events, &replay.app_events),
+            None => (network_events, app_events),
+        };
+
+        let mut clicked = None;
         ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
             let available_width = ui.available_width();
             let available_height = ui.available_height();
@@ -50,14 +303,31 @@ impl NetworkView {
                 |ui| {
                     // First vertical block
                     ui.allocate_ui(egui::vec2(available_width * 0.45, available_height), |ui| {
-                        show_events_in_columns(ui, "App Events", app_events);
+                        clicked = clicked.take().or(self.show_events_in_columns(
+                            ui,
+                            "App Events",
+                            app_events,
+                            &peer_names,
+                        ));
                     });
                     // Second vertical block
                     ui.allocate_ui(egui::vec2(available_width * 0.55, available_height), |ui| {
-                        show_events_in_columns(ui, "Network Events", network_events);
+                        clicked = clicked.take().or(self.show_events_in_columns(
+                            ui,
+                            "Network Events",
+                            network_events,
+                            &peer_names,
+                        ));
                     });
                 },
             );
         });
+        clicked
+    }
+}
+
+impl Default for NetworkView {
+    fn default() -> Self {
+        Self::new()
     }
 }
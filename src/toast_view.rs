@@ -0,0 +1,321 @@
+use std::collections::BTreeSet;
+
+use dtchat_backend::message::ChatMessage;
+use dtchat_backend::time::DTChatTime;
+use eframe::egui;
+use egui::{Color32, RichText};
+
+use crate::app::HandlerUpdate;
+use crate::main_view::MirroredData;
+use crate::messages::unread::conversation_uuid;
+use crate::utils::history::TransitionKind;
+
+// how long a toast stays on screen, failures stay longer
+const TOAST_DURATION_MS: i64 = 6000;
+const FAILURE_TOAST_DURATION_MS: i64 = 12000;
+// older toasts are dropped
+const MAX_TOASTS: usize = 5;
+const TOAST_WIDTH: f32 = 280.0;
+// characters of the message content shown in a toast
+const PREVIEW_CHARS: usize = 60;
+
+#[derive(Clone, PartialEq)]
+pub enum ToastKind {
+    Arrival,
+    Delivered,
+    // socket failure: what failed, on which endpoint and why
+    Failed {
+        what: &'static str,
+        endpoint: String,
+        reason: String,
+    },
+}
+
+impl ToastKind {
+    fn color(&self) -> Color32 {
+        match self {
+            ToastKind::Arrival => Color32::LIGHT_BLUE,
+            ToastKind::Delivered => Color32::GREEN,
+            ToastKind::Failed { .. } => Color32::RED,
+        }
+    }
+
+    fn duration_ms(&self) -> i64 {
+        match self {
+            ToastKind::Failed { .. } => FAILURE_TOAST_DURATION_MS,
+            _ => TOAST_DURATION_MS,
+        }
+    }
+}
+
+/// What the update announces, with the uuid of the message it is about when known
+pub struct PendingToast {
+    kind: ToastKind,
+    msg_uuid: Option<String>,
+}
+
+/// Taken before the update is applied to the mirrored data. Every socket failure is
+/// announced, with its message only when the token is the uuid of a mirrored message.
+pub fn pending_toasts(update: &HandlerUpdate) -> Vec<PendingToast> {
+    let mut pending = Vec::new();
+    for (msg_uuid, transition) in &update.message_transitions {
+        let kind = match transition.kind {
+            TransitionKind::Received => ToastKind::Arrival,
+            TransitionKind::AckReceived => ToastKind::Delivered,
+            _ => continue,
+        };
+        pending.push(PendingToast {
+            kind,
+            msg_uuid: Some(msg_uuid.clone()),
+        });
+    }
+    for failure in &update.socket_failures {
+        pending.push(PendingToast {
+            kind: ToastKind::Failed {
+                what: failure.kind.name(),
+                endpoint: failure.endpoint.clone(),
+                reason: failure.reason.clone(),
+            },
+            // looked up in the mirrored messages by `notify`
+            msg_uuid: Some(failure.token.clone()),
+        });
+    }
+    pending
+}
+
+fn preview(msg: &ChatMessage) -> String {
+    let text = msg.content_as_string().replace('\n', " ");
+    if text.chars().count() > PREVIEW_CHARS {
+        let mut preview: String = text.chars().take(PREVIEW_CHARS).collect();
+        preview.push('\u{2026}');
+        preview
+    } else {
+        text
+    }
+}
+
+fn conversation_name(data: &MirroredData, uuid: &str) -> String {
+    match (data.rooms.get(uuid), data.other_peers.get(uuid)) {
+        (Some(room), _) => format!("\u{1F465} {}", room.name),
+        (None, Some(peer)) => format!("\u{1F464} {}", peer.name),
+        (None, None) => "Unknown".to_string(),
+    }
+}
+
+struct Toast {
+    kind: ToastKind,
+    msg_uuid: Option<String>,
+    title: String,
+    text: String,
+    created_ms: i64,
+    // identical toasts received while this one was shown
+    repeats: usize,
+}
+
+impl Toast {
+    fn about_message(
+        kind: ToastKind,
+        msg: &ChatMessage,
+        conversation: &str,
+        data: &MirroredData,
+    ) -> Self {
+        let title = match &kind {
+            ToastKind::Arrival => {
+                let sender = match data.other_peers.get(&msg.sender_uuid) {
+                    Some(peer) => peer.name.clone(),
+                    None => "Unknown".to_string(),
+                };
+                format!(
+                    "\u{2709} {} in {}",
+                    sender,
+                    conversation_name(data, conversation)
+                )
+            }
+            ToastKind::Delivered => {
+                format!(
+                    "\u{2714} Delivered to {}",
+                    conversation_name(data, conversation)
+                )
+            }
+            ToastKind::Failed { what, .. } => {
+                format!(
+                    "\u{26A0} {} to {}",
+                    what,
+                    conversation_name(data, conversation)
+                )
+            }
+        };
+        let text = match &kind {
+            ToastKind::Failed {
+                endpoint, reason, ..
+            } => format!("{} ({}): {}", reason, endpoint, preview(msg)),
+            _ => preview(msg),
+        };
+        Self::new(kind, Some(msg.uuid.clone()), title, text)
+    }
+
+    /// A failure whose token is not linked to a message
+    fn unlinked_failure(kind: ToastKind) -> Self {
+        let (title, text) = match &kind {
+            ToastKind::Failed {
+                what,
+                endpoint,
+                reason,
+            } => (format!("\u{26A0} {} on {}", what, endpoint), reason.clone()),
+            _ => (String::new(), String::new()),
+        };
+        Self::new(kind, None, title, text)
+    }
+
+    fn new(kind: ToastKind, msg_uuid: Option<String>, title: String, text: String) -> Self {
+        Self {
+            kind,
+            msg_uuid,
+            title,
+            text,
+            created_ms: DTChatTime::now().timestamp_millis(),
+            repeats: 0,
+        }
+    }
+}
+
+/// Short-lived notifications in the corner of the window
+pub struct ToastView {
+    toasts: Vec<Toast>,
+    pub do_not_disturb: bool,
+}
+
+impl ToastView {
+    pub fn new() -> Self {
+        Self {
+            toasts: Vec::new(),
+            do_not_disturb: false,
+        }
+    }
+
+    /// Turns the pending announcements into toasts. Arrivals in the conversation being
+    /// viewed and everything about a muted conversation are skipped.
+    pub fn notify(
+        &mut self,
+        pending: Vec<PendingToast>,
+        data: &MirroredData,
+        viewed: Option<&str>,
+        muted: &BTreeSet<String>,
+    ) {
+        if self.do_not_disturb {
+            return;
+        }
+        for PendingToast { kind, msg_uuid } in pending {
+            let msg = msg_uuid
+                .as_ref()
+                .and_then(|uuid| data.messages.iter().find(|msg| msg.uuid == *uuid));
+            let toast = match msg {
+                Some(msg) => {
+                    let conversation = conversation_uuid(msg, data);
+                    let is_local = msg.sender_uuid == data.local_peer.uuid;
+                    let skipped = match kind {
+                        ToastKind::Arrival => is_local || viewed == Some(conversation.as_str()),
+                        ToastKind::Delivered => !is_local,
+                        ToastKind::Failed { .. } => false,
+                    };
+                    if skipped || muted.contains(&conversation) {
+                        continue;
+                    }
+                    Toast::about_message(kind, msg, &conversation, data)
+                }
+                None if matches!(kind, ToastKind::Failed { .. }) => Toast::unlinked_failure(kind),
+                None => continue,
+            };
+            // the same failure repeated is counted on the toast on screen
+            let shown = self
+                .toasts
+                .iter_mut()
+                .find(|shown| shown.msg_uuid == toast.msg_uuid && shown.kind == toast.kind);
+            match shown {
+                Some(shown) => {
+                    shown.repeats += 1;
+                    shown.created_ms = toast.created_ms;
+                }
+                None => self.toasts.push(toast),
+            }
+        }
+        let overflow = self.toasts.len().saturating_sub(MAX_TOASTS);
+        self.toasts.drain(..overflow);
+    }
+
+    pub fn show_toggle(&mut self, ui: &mut egui::Ui) {
+        let (icon, hover) = if self.do_not_disturb {
+            ("\u{1F515}", "Do not disturb: notifications are off")
+        } else {
+            (
+                "\u{1F514}",
+                "Notifications are on, click for do not disturb",
+            )
+        };
+        if ui
+            .selectable_label(self.do_not_disturb, icon)
+            .on_hover_text(hover)
+            .clicked()
+        {
+            self.do_not_disturb = !self.do_not_disturb;
+            if self.do_not_disturb {
+                self.toasts.clear();
+            }
+        }
+    }
+
+    /// Returns the uuid of the message whose toast was clicked
+    pub fn show(&mut self, ctx: &egui::Context, current_time: &DTChatTime) -> Option<String> {
+        let now_ms = current_time.timestamp_millis();
+        self.toasts
+            .retain(|toast| now_ms - toast.created_ms < toast.kind.duration_ms());
+        if self.toasts.is_empty() {
+            return None;
+        }
+
+        let mut clicked = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (idx, toast) in self.toasts.iter().enumerate() {
+                    let response = egui::Frame::popup(ui.style())
+                        .show(ui, |ui| {
+                            ui.set_width(TOAST_WIDTH);
+                            ui.label(
+                                RichText::new(&toast.title)
+                                    .color(toast.kind.color())
+                                    .strong(),
+                            );
+                            ui.label(&toast.text);
+                            if toast.repeats > 0 {
+                                ui.colored_label(
+                                    Color32::GRAY,
+                                    format!("{} more times", toast.repeats),
+                                );
+                            }
+                        })
+                        .response
+                        .interact(egui::Sense::click());
+                    let response = match toast.msg_uuid {
+                        Some(_) => response.on_hover_text("Click to show the message"),
+                        None => response.on_hover_text(
+                            "The failure could not be linked with certainty to a message, \
+                             click to dismiss",
+                        ),
+                    };
+                    if response.clicked() {
+                        clicked = Some(idx);
+                    }
+                    ui.add_space(4.0);
+                }
+            });
+        clicked.and_then(|idx| self.toasts.remove(idx).msg_uuid)
+    }
+}
+
+impl Default for ToastView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,116 @@
+use egui::Color32;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+/// The subset of the backend configuration file (`CONFIG_PATH`) the GUI reads on its own.
+#[derive(Deserialize, Default, Clone)]
+pub struct GuiConfig {
+    pub cp_path: Option<String>,
+    #[serde(default)]
+    pub peer_list: Vec<PeerConfig>,
+    #[serde(default)]
+    pub room_list: Vec<RoomConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct PeerConfig {
+    pub uuid: String,
+    pub color: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RoomConfig {
+    pub uuid: String,
+    #[serde(default)]
+    pub participants: Vec<ParticipantConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ParticipantConfig {
+    pub peer_uuid: String,
+    // endpoint of the peer for the room, "bp ipn:20.2"
+    pub endpoint: Option<String>,
+}
+
+/// Accepts the egui color names used in the configuration (`MAGENTA`, `LIGHT_BLUE`...)
+/// or an html `#rrggbb` code.
+pub fn parse_color(name: &str) -> Option<Color32> {
+    if name.starts_with('#') {
+        return Color32::from_hex(name).ok();
+    }
+    let color = match name.to_uppercase().as_str() {
+        "BLACK" => Color32::BLACK,
+        "DARK_GRAY" => Color32::DARK_GRAY,
+        "GRAY" => Color32::GRAY,
+        "LIGHT_GRAY" => Color32::LIGHT_GRAY,
+        "WHITE" => Color32::WHITE,
+        "BROWN" => Color32::BROWN,
+        "DARK_RED" => Color32::DARK_RED,
+        "RED" => Color32::RED,
+        "LIGHT_RED" => Color32::LIGHT_RED,
+        "CYAN" => Color32::CYAN,
+        "MAGENTA" => Color32::MAGENTA,
+        "YELLOW" => Color32::YELLOW,
+        "ORANGE" => Color32::ORANGE,
+        "LIGHT_YELLOW" => Color32::LIGHT_YELLOW,
+        "KHAKI" => Color32::KHAKI,
+        "DARK_GREEN" => Color32::DARK_GREEN,
+        "GREEN" => Color32::GREEN,
+        "LIGHT_GREEN" => Color32::LIGHT_GREEN,
+        "DARK_BLUE" => Color32::DARK_BLUE,
+        "BLUE" => Color32::BLUE,
+        "LIGHT_BLUE" => Color32::LIGHT_BLUE,
+        "PURPLE" => Color32::PURPLE,
+        "GOLD" => Color32::GOLD,
+        _ => return None,
+    };
+    Some(color)
+}
+
+impl GuiConfig {
+    pub fn load() -> Self {
+        let mut config = match env::var("CONFIG_PATH") {
+            Ok(path) => match fs::read_to_string(&path) {
+                Ok(text) => match serde_yaml::from_str::<GuiConfig>(&text) {
+                    Ok(config) => config,
+                    Err(err) => {
+                        elog_with_location!("Unable to parse {}: {}", path, err);
+                        GuiConfig::default()
+                    }
+                },
+                Err(err) => {
+                    elog_with_location!("Unable to read {}: {}", path, err);
+                    GuiConfig::default()
+                }
+            },
+            Err(_) => GuiConfig::default(),
+        };
+
+        // CP_PATH takes precedence, as for the backend
+        if let Ok(cp_path) = env::var("CP_PATH") {
+            config.cp_path = Some(cp_path);
+        }
+        config
+    }
+
+    /// peer uuid -> configured color
+    pub fn peer_colors(&self) -> HashMap<String, Color32> {
+        self.peer_list
+            .iter()
+            .filter_map(|peer| {
+                let color = parse_color(peer.color.as_ref()?)?;
+                Some((peer.uuid.clone(), color))
+            })
+            .collect()
+    }
+
+    /// room uuid -> participating peers and their endpoint for the room
+    pub fn room_participants(&self) -> HashMap<String, Vec<ParticipantConfig>> {
+        self.room_list
+            .iter()
+            .map(|room| (room.uuid.clone(), room.participants.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::fs;
+
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use dtchat_backend::{dtchat::Peer, time::DTChatTime, Endpoint, EndpointProto};
+
+/// `a contact <start> <end> <from> <to> <rate>`: a transmission window between two nodes.
+/// Times are in milliseconds since the epoch, the rate in bytes per second.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contact {
+    pub start: i64,
+    pub end: i64,
+    pub from: u64,
+    pub to: u64,
+    pub rate: f64,
+}
+
+/// `a range <start> <end> <from> <to> <owlt>`: the one-way light time (seconds) between two nodes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContactRange {
+    pub start: i64,
+    pub end: i64,
+    pub from: u64,
+    pub to: u64,
+    pub owlt: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ContactPlan {
+    pub path: String,
+    pub contacts: Vec<Contact>,
+    pub ranges: Vec<ContactRange>,
+}
+
+/// ION accepts relative times (`+3600`, seconds after the plan was loaded)
+/// and absolute UTC times (`2025/07/01-12:00:00`).
+fn parse_time(token: &str, reference_ms: i64) -> Result<i64, String> {
+    if let Some(offset) = token.strip_prefix('+') {
+        let secs: i64 = offset
+            .parse()
+            .map_err(|_| format!("invalid relative time \"{}\"", token))?;
+        return Ok(reference_ms + secs * 1000);
+    }
+    NaiveDateTime::parse_from_str(token, "%Y/%m/%d-%H:%M:%S")
+        .map(|dt| Utc.from_utc_datetime(&dt).timestamp_millis())
+        .map_err(|_| format!("invalid time \"{}\"", token))
+}
+
+fn time_field(token: Option<&&str>, reference_ms: i64, what: &str) -> Result<i64, String> {
+    match token {
+        Some(token) => parse_time(token, reference_ms),
+        None => Err(format!("missing {}", what)),
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(token: Option<&&str>, what: &str) -> Result<T, String> {
+    match token {
+        Some(token) => token
+            .parse()
+            .map_err(|_| format!("invalid {} \"{}\"", what, token)),
+        None => Err(format!("missing {}", what)),
+    }
+}
+
+impl ContactPlan {
+    pub fn parse(text: &str, reference: &DTChatTime) -> Result<Self, String> {
+        let reference_ms = reference.timestamp_millis();
+        let mut plan = ContactPlan::default();
+
+        for (line_idx, line) in text.lines().enumerate() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let with_line = |err: String| format!("line {}: {}", line_idx + 1, err);
+
+            match tokens.as_slice() {
+                ["a", "contact", ..] => {
+                    let contact = Contact {
+                        start: time_field(tokens.get(2), reference_ms, "start time")
+                            .map_err(with_line)?,
+                        end: time_field(tokens.get(3), reference_ms, "end time")
+                            .map_err(with_line)?,
+                        from: parse_field(tokens.get(4), "source node").map_err(with_line)?,
+                        to: parse_field(tokens.get(5), "destination node").map_err(with_line)?,
+                        rate: parse_field(tokens.get(6), "data rate").map_err(with_line)?,
+                    };
+                    plan.contacts.push(contact);
+                }
+                ["a", "range", ..] => {
+                    let range = ContactRange {
+                        start: time_field(tokens.get(2), reference_ms, "start time")
+                            .map_err(with_line)?,
+                        end: time_field(tokens.get(3), reference_ms, "end time")
+                            .map_err(with_line)?,
+                        from: parse_field(tokens.get(4), "source node").map_err(with_line)?,
+                        to: parse_field(tokens.get(5), "destination node").map_err(with_line)?,
+                        owlt: parse_field(tokens.get(6), "one-way light time")
+                            .map_err(with_line)?,
+                    };
+                    plan.ranges.push(range);
+                }
+                // comments, blank lines and other ionadmin commands are not part of the plan
+                _ => {}
+            }
+        }
+
+        Ok(plan)
+    }
+
+    pub fn load(path: &str, reference: &DTChatTime) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        let mut plan = Self::parse(&text, reference).map_err(|err| format!("{}: {}", path, err))?;
+        plan.path = path.to_string();
+        Ok(plan)
+    }
+
+    /// Windows (start, end) of the contacts from any of `from_nodes` to any of `to_nodes`.
+    pub fn contact_windows(&self, from_nodes: &[u64], to_nodes: &[u64]) -> Vec<(i64, i64)> {
+        self.contacts
+            .iter()
+            .filter(|c| from_nodes.contains(&c.from) && to_nodes.contains(&c.to))
+            .map(|c| (c.start, c.end))
+            .collect()
+    }
+
+    /// Earliest time at or after `after` when a contact from `from_nodes` to `to_nodes` is open.
+    pub fn next_contact_start(
+        &self,
+        from_nodes: &[u64],
+        to_nodes: &[u64],
+        after: i64,
+    ) -> Option<i64> {
+        self.contact_windows(from_nodes, to_nodes)
+            .into_iter()
+            .filter(|(_, end)| *end > after)
+            .map(|(start, _)| start.max(after))
+            .min()
+    }
+
+    /// One-way light time in ms from `from` to `to` at `at`. The range of that direction comes
+    /// first, a range of the reverse direction only applies when there is none.
+    pub fn owlt_ms(&self, from: u64, to: u64, at: i64) -> i64 {
+        let open = |r: &&ContactRange| r.start <= at && at < r.end;
+        self.ranges
+            .iter()
+            .filter(open)
+            .find(|r| r.from == from && r.to == to)
+            .or_else(|| {
+                self.ranges
+                    .iter()
+                    .filter(open)
+                    .find(|r| r.from == to && r.to == from)
+            })
+            .map_or(0, |r| r.owlt as i64 * 1000)
+    }
+
+    /// Earliest arrival time at one of `to_nodes` for a bundle handed over at `send_ms`,
+    /// following the contacts (store and forward) and ignoring their capacity.
+    pub fn earliest_arrival(
+        &self,
+        from_nodes: &[u64],
+        to_nodes: &[u64],
+        send_ms: i64,
+    ) -> Option<i64> {
+        let mut arrival: HashMap<u64, i64> = from_nodes.iter().map(|n| (*n, send_ms)).collect();
+        let mut visited: Vec<u64> = Vec::new();
+
+        // Dijkstra on the arrival times, the plans are small enough for a linear scan
+        loop {
+            let (node, time) = arrival
+                .iter()
+                .filter(|(node, _)| !visited.contains(node))
+                .min_by_key(|(_, time)| **time)
+                .map(|(node, time)| (*node, *time))?;
+            if to_nodes.contains(&node) {
+                return Some(time);
+            }
+            visited.push(node);
+
+            for contact in self
+                .contacts
+                .iter()
+                .filter(|c| c.from == node && c.end > time)
+            {
+                let departure = contact.start.max(time);
+                let arrives = departure + self.owlt_ms(contact.from, contact.to, departure);
+                let best = arrival.entry(contact.to).or_insert(i64::MAX);
+                if arrives < *best {
+                    *best = arrives;
+                }
+            }
+        }
+    }
+}
+
+/// `ipn:10.2` -> `10`
+fn eid_node(eid: &str) -> Option<u64> {
+    let node = eid.strip_prefix("ipn:")?.split('.').next()?;
+    node.parse().ok()
+}
+
+/// `bp ipn:10.2` -> `10`
+pub fn ipn_node(endpoint: &Endpoint) -> Option<u64> {
+    if endpoint.proto != EndpointProto::Bp {
+        return None;
+    }
+    eid_node(&endpoint.endpoint)
+}
+
+/// Node of an endpoint written as in the configuration, `"bp ipn:10.2"` -> `10`. None for
+/// the tcp and udp endpoints.
+pub fn configured_node(endpoint: &str) -> Option<u64> {
+    match endpoint.trim().split_once(' ')? {
+        ("bp", eid) => eid_node(eid.trim()),
+        _ => None,
+    }
+}
+
+pub fn peer_nodes(peer: &Peer) -> Vec<u64> {
+    peer.endpoints.iter().filter_map(ipn_node).collect()
+}
+
+/// ipn node number -> peer name, built from the bp endpoints of the peers.
+pub fn node_names(local_peer: &Peer, other_peers: &HashMap<String, Peer>) -> HashMap<u64, String> {
+    let mut names = HashMap::new();
+    for peer in std::iter::once(local_peer).chain(other_peers.values()) {
+        for endpoint in &peer.endpoints {
+            if let Some(node) = ipn_node(endpoint) {
+                names.insert(node, peer.name.clone());
+            }
+        }
+    }
+    names
+}
+
+pub fn node_name(names: &HashMap<u64, String>, node: u64) -> String {
+    match names.get(&node) {
+        Some(name) => name.clone(),
+        None => format!("ipn:{}", node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // relative times of the plans below are seconds after 0
+    fn plan(text: &str) -> ContactPlan {
+        let reference = DTChatTime::from_timestamp_millis(0).unwrap();
+        ContactPlan::parse(text, &reference).unwrap()
+    }
+
+    #[test]
+    fn direct_contact_open_at_send_time() {
+        let plan = plan("a contact +0 +100 1 2 1000\na range +0 +100 1 2 2");
+        assert_eq!(plan.earliest_arrival(&[1], &[2], 10_000), Some(12_000));
+    }
+
+    #[test]
+    fn bundle_waits_for_the_next_window() {
+        let plan = plan("a contact +50 +100 1 2 1000\na range +0 +100 1 2 1");
+        assert_eq!(plan.earliest_arrival(&[1], &[2], 10_000), Some(51_000));
+        assert_eq!(plan.next_contact_start(&[1], &[2], 10_000), Some(50_000));
+    }
+
+    #[test]
+    fn bundle_is_stored_and_forwarded_by_a_relay() {
+        let plan = plan(
+            "a contact +0 +10 1 3 1000\n\
+             a contact +20 +30 3 2 1000\n\
+             a contact +100 +200 1 2 1000\n\
+             a range +0 +200 1 3 1\n\
+             a range +0 +200 3 2 1",
+        );
+        // through 3 (arrives at 21 s) rather than the direct contact opening at 100 s
+        assert_eq!(plan.earliest_arrival(&[1], &[2], 0), Some(21_000));
+    }
+
+    #[test]
+    fn ranges_apply_in_both_directions() {
+        let plan = plan("a contact +0 +100 2 1 1000\na range +0 +100 1 2 3");
+        assert_eq!(plan.earliest_arrival(&[2], &[1], 0), Some(3_000));
+    }
+
+    #[test]
+    fn the_range_of_the_direction_comes_first() {
+        // the asymmetric ranges between 10 and 20 of db/ion.cp
+        let plan = plan(
+            "a contact +0 +100 10 20 1000\n\
+             a contact +0 +100 20 10 1000\n\
+             a range +0 +100 10 20 7\n\
+             a range +0 +100 20 10 3",
+        );
+        assert_eq!(plan.owlt_ms(10, 20, 0), 7_000);
+        assert_eq!(plan.owlt_ms(20, 10, 0), 3_000);
+        assert_eq!(plan.earliest_arrival(&[10], &[20], 0), Some(7_000));
+        assert_eq!(plan.earliest_arrival(&[20], &[10], 0), Some(3_000));
+    }
+
+    #[test]
+    fn configured_endpoints_of_bp_only_have_a_node() {
+        assert_eq!(configured_node("bp ipn:20.2"), Some(20));
+        assert_eq!(configured_node("tcp 127.0.0.1:7500"), None);
+        assert_eq!(configured_node("udp 127.0.0.1:7550"), None);
+    }
+
+    #[test]
+    fn closed_contacts_give_no_route() {
+        let plan = plan("a contact +0 +10 1 2 1000");
+        assert_eq!(plan.earliest_arrival(&[1], &[2], 20_000), None);
+        assert_eq!(plan.earliest_arrival(&[1], &[3], 0), None);
+        assert_eq!(plan.next_contact_start(&[1], &[2], 20_000), None);
+    }
+
+    #[test]
+    fn the_chosen_destination_node_only() {
+        let plan = plan("a contact +0 +100 1 2 1000\na contact +50 +100 1 4 1000");
+        // the same peer reached through its node 4 rather than 2
+        assert_eq!(plan.earliest_arrival(&[1], &[4], 0), Some(50_000));
+        assert_eq!(plan.earliest_arrival(&[1], &[2, 4], 0), Some(0));
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+
+use dtchat_backend::time::DTChatTime;
+
+use crate::utils::font::{precise_time_str, PrettyStr};
+use crate::utils::uuid::safe_id_display;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SocketEventKind {
+    Sending,
+    Sent,
+    SendFailed,
+    ConnectionFailed,
+}
+
+impl SocketEventKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SocketEventKind::Sending => "Sending",
+            SocketEventKind::Sent => "Sent",
+            SocketEventKind::SendFailed => "Send failed",
+            SocketEventKind::ConnectionFailed => "Connection failed",
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            SocketEventKind::SendFailed | SocketEventKind::ConnectionFailed
+        )
+    }
+}
+
+/// A socket engine event attributed to a chat message
+#[derive(Clone, Debug)]
+pub struct SocketRecord {
+    pub kind: SocketEventKind,
+    pub token: String,
+    pub endpoint: String,
+    pub bytes: Option<usize>,
+    pub error: Option<String>,
+    pub timestamp: DTChatTime,
+}
+
+impl PrettyStr for SocketRecord {
+    fn to_pretty_str(&self) -> String {
+        let mut text = format!(
+            "{} {} ({}, token {})",
+            precise_time_str(&self.timestamp),
+            self.kind.name(),
+            self.endpoint,
+            safe_id_display(&self.token)
+        );
+        if let Some(bytes) = self.bytes {
+            text.push_str(&format!(", {} bytes", bytes));
+        }
+        if let Some(error) = &self.error {
+            text.push_str(&format!(": {}", error));
+        }
+        text
+    }
+}
+
+/// A send or connection failure of the socket engine
+#[derive(Clone, Debug)]
+pub struct SocketFailure {
+    pub kind: SocketEventKind,
+    pub endpoint: String,
+    pub reason: String,
+    pub token: String,
+}
+
+/// Socket records for each message uuid, in arrival order.
+///
+/// A record is linked to the message whose uuid is its token, looked up in the mirrored
+/// messages. The other tokens, such as the ones of the acks or of each recipient of a room,
+/// are not linked: nothing else ties them to a message.
+#[derive(Default)]
+pub struct SocketLog {
+    records: HashMap<String, Vec<SocketRecord>>,
+}
+
+impl SocketLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the records whose token is one of `messages`
+    pub fn extend(&mut self, records: Vec<SocketRecord>, messages: &HashSet<&str>) {
+        for record in records {
+            if messages.contains(record.token.as_str()) {
+                self.records
+                    .entry(record.token.clone())
+                    .or_default()
+                    .push(record);
+            }
+        }
+    }
+
+    pub fn get(&self, msg_uuid: &str) -> &[SocketRecord] {
+        match self.records.get(msg_uuid) {
+            Some(records) => records,
+            None => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: SocketEventKind, token: &str) -> SocketRecord {
+        SocketRecord {
+            kind,
+            token: token.to_string(),
+            endpoint: "tcp 127.0.0.1:7500".to_string(),
+            bytes: None,
+            error: None,
+            timestamp: DTChatTime::from_timestamp_millis(0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn records_are_linked_to_the_message_of_their_token() {
+        let messages = HashSet::from(["a", "b"]);
+        let mut log = SocketLog::new();
+        log.extend(
+            vec![
+                record(SocketEventKind::Sending, "a"),
+                record(SocketEventKind::Sent, "a"),
+                record(SocketEventKind::SendFailed, "b"),
+            ],
+            &messages,
+        );
+        let kinds: Vec<_> = log.get("a").iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, [SocketEventKind::Sending, SocketEventKind::Sent]);
+        assert_eq!(log.get("b").len(), 1);
+    }
+
+    #[test]
+    fn other_tokens_stay_unlinked() {
+        let messages = HashSet::from(["a"]);
+        let mut log = SocketLog::new();
+        log.extend(vec![record(SocketEventKind::Sent, "ack-of-a")], &messages);
+        assert!(log.get("a").is_empty());
+        assert!(log.get("ack-of-a").is_empty());
+    }
+}
@@ -1,4 +1,5 @@
-use dtchat_backend::{message::MessageStatus, Endpoint, EndpointProto};
+use chrono::{DateTime, Local};
+use dtchat_backend::{message::MessageStatus, time::DTChatTime, Endpoint, EndpointProto};
 use egui::Color32;
 pub trait PrettyStr {
     fn to_pretty_str(&self) -> String;
@@ -14,6 +15,24 @@ impl PrettyStr for EndpointProto {
     }
 }
 
+/// Plain protocol name, as in the configuration ("udp", "tcp", "bp")
+pub fn proto_name(proto: &EndpointProto) -> &'static str {
+    match proto {
+        EndpointProto::Udp => "udp",
+        EndpointProto::Tcp => "tcp",
+        EndpointProto::Bp => "bp",
+    }
+}
+
+pub fn proto_from_name(name: &str) -> Option<EndpointProto> {
+    match name {
+        "udp" => Some(EndpointProto::Udp),
+        "tcp" => Some(EndpointProto::Tcp),
+        "bp" => Some(EndpointProto::Bp),
+        _ => None,
+    }
+}
+
 impl PrettyStr for Endpoint {
     fn to_pretty_str(&self) -> String {
         format!(
@@ -72,3 +91,36 @@ impl StatusDisplayHelper for MessageStatus {
         }
     }
 }
+
+/// Human readable duration, e.g. "850 ms", "12.40 s", "3 min 07 s", "1 h 02 min"
+pub fn pretty_duration(ms: i64) -> String {
+    let sign = if ms < 0 { "-" } else { "" };
+    let abs = ms.unsigned_abs();
+    if abs < 1_000 {
+        format!("{}{} ms", sign, abs)
+    } else if abs < 60_000 {
+        format!("{}{:.2} s", sign, abs as f64 / 1_000.0)
+    } else if abs < 3_600_000 {
+        format!(
+            "{}{} min {:02} s",
+            sign,
+            abs / 60_000,
+            (abs % 60_000) / 1_000
+        )
+    } else {
+        format!(
+            "{}{} h {:02} min",
+            sign,
+            abs / 3_600_000,
+            (abs % 3_600_000) / 60_000
+        )
+    }
+}
+
+/// Local time with millisecond precision, for events that are often closer than a second
+pub fn precise_time_str(time: &DTChatTime) -> String {
+    match DateTime::from_timestamp_millis(time.timestamp_millis()) {
+        Some(dt) => dt.with_timezone(&Local).format("%H:%M:%S%.3f").to_string(),
+        None => "???".to_string(),
+    }
+}
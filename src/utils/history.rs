@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use dtchat_backend::message::{ChatMessage, MessageStatus};
+use dtchat_backend::time::DTChatTime;
+
+use crate::utils::font::{precise_time_str, PrettyStr};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransitionKind {
+    Sending,
+    Sent,
+    Received,
+    AckSent,
+    AckReceived,
+    Failed,
+}
+
+impl TransitionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransitionKind::Sending => "Sending",
+            TransitionKind::Sent => "Sent",
+            TransitionKind::Received => "Received",
+            TransitionKind::AckSent => "Ack sent",
+            TransitionKind::AckReceived => "Acked",
+            TransitionKind::Failed => "Failed",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StatusTransition {
+    pub kind: TransitionKind,
+    pub timestamp: DTChatTime,
+}
+
+impl StatusTransition {
+    pub fn new(kind: TransitionKind, timestamp: DTChatTime) -> Self {
+        Self { kind, timestamp }
+    }
+
+    pub fn time_str(&self) -> String {
+        precise_time_str(&self.timestamp)
+    }
+}
+
+impl PrettyStr for StatusTransition {
+    fn to_pretty_str(&self) -> String {
+        format!("{} {}", self.kind.name(), self.time_str())
+    }
+}
+
+/// Status transitions seen for each message uuid, in arrival order.
+#[derive(Default)]
+pub struct MessageHistory {
+    transitions: HashMap<String, Vec<StatusTransition>>,
+}
+
+impl MessageHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, msg_uuid: &str, transition: StatusTransition) {
+        self.transitions
+            .entry(msg_uuid.to_string())
+            .or_default()
+            .push(transition);
+    }
+
+    pub fn extend(&mut self, transitions: Vec<(String, StatusTransition)>) {
+        for (msg_uuid, transition) in transitions {
+            self.record(&msg_uuid, transition);
+        }
+    }
+
+    /// The model does not notify failures as chat events: a message seen failed whose
+    /// last transition is not a failure gets one, dated `timestamp`. Returns the uuids of
+    /// these messages.
+    pub fn record_failures(
+        &mut self,
+        messages: &[ChatMessage],
+        timestamp: DTChatTime,
+    ) -> Vec<String> {
+        let mut failed = Vec::new();
+        for msg in messages {
+            if msg.status != MessageStatus::Failed {
+                continue;
+            }
+            let last = self.get(&msg.uuid).last().map(|t| t.kind);
+            if last != Some(TransitionKind::Failed) {
+                let transition = StatusTransition::new(TransitionKind::Failed, timestamp);
+                self.record(&msg.uuid, transition);
+                failed.push(msg.uuid.clone());
+            }
+        }
+        failed
+    }
+
+    pub fn get(&self, msg_uuid: &str) -> &[StatusTransition] {
+        match self.transitions.get(msg_uuid) {
+            Some(transitions) => transitions,
+            None => &[],
+        }
+    }
+
+    /// "Sending 12:00:01.000 → Sent 12:00:01.200 → Acked 12:14:33.050"
+    pub fn describe(&self, msg_uuid: &str) -> Option<String> {
+        let transitions = self.get(msg_uuid);
+        if transitions.is_empty() {
+            return None;
+        }
+        Some(
+            transitions
+                .iter()
+                .map(|t| t.to_pretty_str())
+                .collect::<Vec<_>>()
+                .join(" → "),
+        )
+    }
+}
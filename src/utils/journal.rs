@@ -0,0 +1,275 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use dtchat_backend::time::DTChatTime;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{DisplayEvent, EventLevel};
+use crate::utils::font::{proto_from_name, proto_name};
+
+// rotate once the current file reaches this size
+const MAX_JOURNAL_BYTES: u64 = 4 * 1024 * 1024;
+// current file + rotated ones
+const MAX_JOURNAL_FILES: usize = 5;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventCategory {
+    App,
+    Network,
+}
+
+/// One line of the journal
+#[derive(Serialize, Deserialize)]
+struct JournalEntry {
+    category: EventCategory,
+    level: EventLevel,
+    timestamp_ms: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peer_uuid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    endpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    protocol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_uuid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes: Option<usize>,
+}
+
+impl JournalEntry {
+    fn from_event(category: EventCategory, event: &DisplayEvent) -> Self {
+        Self {
+            category,
+            level: event.level,
+            timestamp_ms: event.timestamp.timestamp_millis(),
+            message: event.message.clone(),
+            peer_uuid: event.peer_uuid.clone(),
+            endpoint: event.endpoint.clone(),
+            protocol: event.protocol.as_ref().map(|p| proto_name(p).to_string()),
+            message_uuid: event.message_uuid.clone(),
+            bytes: event.bytes,
+        }
+    }
+
+    fn into_event(self) -> Option<DisplayEvent> {
+        let mut event = DisplayEvent::new(self.level, self.message);
+        event.timestamp = DTChatTime::from_timestamp_millis(self.timestamp_ms)?;
+        event.peer_uuid = self.peer_uuid;
+        event.endpoint = self.endpoint;
+        event.protocol = self.protocol.as_deref().and_then(proto_from_name);
+        event.message_uuid = self.message_uuid;
+        event.bytes = self.bytes;
+        Some(event)
+    }
+}
+
+/// The journals are written next to the configuration file, or in the working directory
+pub fn journal_dir() -> PathBuf {
+    env::var("CONFIG_PATH")
+        .ok()
+        .and_then(|path| Path::new(&path).parent().map(Path::to_path_buf))
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Append-only JSON-lines log of the displayed events, one journal per local peer.
+///
+/// `events-<peer>.jsonl` is the current file, `events-<peer>.1.jsonl` the previous one, etc.
+pub struct EventJournal {
+    dir: PathBuf,
+    stem: String,
+    file: Option<File>,
+    written: u64,
+}
+
+impl EventJournal {
+    pub fn new(local_peer_uuid: &str) -> Self {
+        let mut journal = Self {
+            dir: journal_dir(),
+            stem: format!("events-{}", local_peer_uuid),
+            file: None,
+            written: 0,
+        };
+        journal.open();
+        journal
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.rotated_path(0)
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        if index == 0 {
+            self.dir.join(format!("{}.jsonl", self.stem))
+        } else {
+            self.dir.join(format!("{}.{}.jsonl", self.stem, index))
+        }
+    }
+
+    fn open(&mut self) {
+        let path = self.path();
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => {
+                self.written = file.metadata().map(|m| m.len()).unwrap_or(0);
+                self.file = Some(file);
+            }
+            Err(err) => {
+                elog_with_location!("Unable to open journal {}: {}", path.display(), err);
+                self.file = None;
+            }
+        }
+    }
+
+    fn rotate(&mut self) {
+        self.file = None;
+        for index in (1..MAX_JOURNAL_FILES).rev() {
+            let from = self.rotated_path(index - 1);
+            if !from.exists() {
+                continue;
+            }
+            // renaming onto an existing file fails on Windows
+            let to = self.rotated_path(index);
+            if to.exists() {
+                if let Err(err) = fs::remove_file(&to) {
+                    elog_with_location!("Unable to remove journal {}: {}", to.display(), err);
+                    continue;
+                }
+            }
+            if let Err(err) = fs::rename(&from, &to) {
+                elog_with_location!("Unable to rotate journal {}: {}", from.display(), err);
+            }
+        }
+        self.open();
+    }
+
+    pub fn append(&mut self, category: EventCategory, event: &DisplayEvent) {
+        if self.written >= MAX_JOURNAL_BYTES {
+            self.rotate();
+        }
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let line = match serde_json::to_string(&JournalEntry::from_event(category, event)) {
+            Ok(line) => line + "\n",
+            Err(err) => {
+                elog_with_location!("Unable to serialize event: {}", err);
+                return;
+            }
+        };
+        match file.write_all(line.as_bytes()) {
+            Ok(()) => self.written += line.len() as u64,
+            Err(err) => {
+                // stop journaling rather than flooding stderr
+                elog_with_location!("Unable to write journal: {}", err);
+                self.file = None;
+            }
+        }
+    }
+}
+
+/// Events of a past session, split by category as in the Network view
+pub struct JournalReplay {
+    pub path: PathBuf,
+    pub app_events: VecDeque<DisplayEvent>,
+    pub network_events: VecDeque<DisplayEvent>,
+    // unreadable lines, left out of the replay
+    pub skipped_lines: usize,
+    pub first_error: Option<String>,
+}
+
+impl JournalReplay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
+
+        let mut replay = Self {
+            path: path.to_path_buf(),
+            app_events: VecDeque::new(),
+            network_events: VecDeque::new(),
+            skipped_lines: 0,
+            first_error: None,
+        };
+        // a line cut by a crash or edited by hand does not hide the rest of the file
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: JournalEntry = match serde_json::from_str(line) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    replay.skip_line(format!("line {}: {}", index + 1, err));
+                    continue;
+                }
+            };
+            let category = entry.category;
+            let Some(event) = entry.into_event() else {
+                replay.skip_line(format!("line {}: invalid timestamp", index + 1));
+                continue;
+            };
+            match category {
+                EventCategory::App => replay.app_events.push_back(event),
+                EventCategory::Network => replay.network_events.push_back(event),
+            }
+        }
+        Ok(replay)
+    }
+
+    fn skip_line(&mut self, error: String) {
+        self.skipped_lines += 1;
+        if self.first_error.is_none() {
+            self.first_error = Some(error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_shifts_the_files_and_drops_the_oldest() {
+        let dir = env::temp_dir().join(format!("dtchat-journal-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut journal = EventJournal {
+            dir: dir.clone(),
+            stem: "events-test".to_string(),
+            file: None,
+            written: 0,
+        };
+        let paths: Vec<PathBuf> = (0..=MAX_JOURNAL_FILES)
+            .map(|index| journal.rotated_path(index))
+            .collect();
+        let content = |index: usize| fs::read_to_string(&paths[index]).unwrap();
+        for (index, path) in paths.iter().take(MAX_JOURNAL_FILES).enumerate() {
+            fs::write(path, format!("file {}\n", index)).unwrap();
+        }
+
+        journal.rotate();
+        assert_eq!(content(0), "");
+        for index in 1..MAX_JOURNAL_FILES {
+            assert_eq!(content(index), format!("file {}\n", index - 1));
+        }
+        assert!(!paths[MAX_JOURNAL_FILES].exists());
+
+        // the new current file is appended to and rotated once full
+        journal.append(
+            EventCategory::App,
+            &DisplayEvent::new(EventLevel::Info, "after".to_string()),
+        );
+        journal.written = MAX_JOURNAL_BYTES;
+        journal.append(
+            EventCategory::App,
+            &DisplayEvent::new(EventLevel::Info, "rotated".to_string()),
+        );
+        assert!(content(1).contains("after"));
+        assert!(content(0).contains("rotated"));
+        assert_eq!(content(2), "file 0\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -3,3 +3,12 @@ pub mod uuid;
 #[macro_use]
 pub mod logging;
 pub mod clock;
+pub mod config;
+pub mod contact_plan;
+pub mod correlation;
+pub mod history;
+pub mod journal;
+pub mod recorder;
+pub mod report;
+pub mod stats;
+pub mod ui_state;
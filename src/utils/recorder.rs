@@ -0,0 +1,383 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use dtchat_backend::event::{
+    ChatAppErrorEvent, ChatAppEvent, ChatAppInfoEvent, ConnectionEvent, DataEvent, ErrorEvent,
+    NetworkErrorEvent, NetworkEvent,
+};
+use dtchat_backend::message::ChatMessage;
+use dtchat_backend::time::DTChatTime;
+use serde::{Deserialize, Serialize};
+
+use crate::app::EventHandler;
+
+/// Serializable copy of a `ChatAppEvent`.
+///
+/// Endpoints and error reasons are kept in their textual form, received payloads
+/// only by their size.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum RecordedEvent {
+    Sending(ChatMessage),
+    Sent(ChatMessage),
+    Received(ChatMessage),
+    AckSent(ChatMessage, String),
+    AckReceived(ChatMessage),
+    ProtocolDecode(String),
+    ProtocolEncode(String),
+    InvalidMessage(String),
+    MessageNotFound(String),
+    PeerNotFound(String),
+    NoEngineAttached,
+    InternalError(String),
+    DataReceived {
+        bytes: usize,
+        from: String,
+    },
+    DataSent {
+        token: String,
+        to: String,
+        bytes: usize,
+    },
+    DataSending {
+        token: String,
+        to: String,
+        bytes: usize,
+    },
+    ListenerStarted {
+        endpoint: String,
+    },
+    ConnectionEstablished {
+        endpoint: String,
+    },
+    ConnectionClosed {
+        endpoint: Option<String>,
+    },
+    ConnectionFailed {
+        endpoint: String,
+        token: String,
+        reason: String,
+    },
+    SendFailed {
+        endpoint: String,
+        token: String,
+        reason: String,
+    },
+    ReceiveFailed {
+        endpoint: String,
+        reason: String,
+    },
+    SocketError {
+        endpoint: String,
+        reason: String,
+    },
+    Info(String),
+}
+
+impl From<ChatAppEvent> for RecordedEvent {
+    fn from(event: ChatAppEvent) -> Self {
+        match event {
+            ChatAppEvent::Message(info_event) => match info_event {
+                ChatAppInfoEvent::Sending(msg) => RecordedEvent::Sending(msg),
+                ChatAppInfoEvent::Sent(msg) => RecordedEvent::Sent(msg),
+                ChatAppInfoEvent::Received(msg) => RecordedEvent::Received(msg),
+                ChatAppInfoEvent::AckSent(msg, peer_id) => {
+                    RecordedEvent::AckSent(msg, peer_id.to_string())
+                }
+                ChatAppInfoEvent::AckReceived(msg) => RecordedEvent::AckReceived(msg),
+            },
+            ChatAppEvent::Error(error_event) => match error_event {
+                ChatAppErrorEvent::ProtocolDecode(error) => RecordedEvent::ProtocolDecode(error),
+                ChatAppErrorEvent::ProtocolEncode(error) => RecordedEvent::ProtocolEncode(error),
+                ChatAppErrorEvent::InvalidMessage(error) => RecordedEvent::InvalidMessage(error),
+                ChatAppErrorEvent::MessageNotFound(msg_id) => {
+                    RecordedEvent::MessageNotFound(msg_id)
+                }
+                ChatAppErrorEvent::PeerNotFound(peer_id) => {
+                    RecordedEvent::PeerNotFound(peer_id.to_string())
+                }
+                ChatAppErrorEvent::NoEngineAttached => RecordedEvent::NoEngineAttached,
+                ChatAppErrorEvent::InternalError(error) => RecordedEvent::InternalError(error),
+            },
+            ChatAppEvent::SocketEngineInfo(network_event) => match network_event {
+                NetworkEvent::Data(data_event) => match data_event {
+                    DataEvent::Received { data, from } => RecordedEvent::DataReceived {
+                        bytes: data.len(),
+                        from: from.to_string(),
+                    },
+                    DataEvent::Sent {
+                        token,
+                        to,
+                        bytes_sent,
+                    } => RecordedEvent::DataSent {
+                        token,
+                        to: to.to_string(),
+                        bytes: bytes_sent,
+                    },
+                    DataEvent::Sending { token, to, bytes } => RecordedEvent::DataSending {
+                        token,
+                        to: to.to_string(),
+                        bytes,
+                    },
+                },
+                NetworkEvent::Connection(connection_event) => match connection_event {
+                    ConnectionEvent::ListenerStarted { endpoint } => {
+                        RecordedEvent::ListenerStarted {
+                            endpoint: endpoint.to_string(),
+                        }
+                    }
+                    ConnectionEvent::Established { remote } => {
+                        RecordedEvent::ConnectionEstablished {
+                            endpoint: remote.endpoint.to_string(),
+                        }
+                    }
+                    ConnectionEvent::Closed { remote } => RecordedEvent::ConnectionClosed {
+                        endpoint: remote.map(|remote| remote.endpoint.to_string()),
+                    },
+                },
+            },
+            ChatAppEvent::SocketEngineError(NetworkErrorEvent::SocketError(socket_error)) => {
+                match socket_error {
+                    ErrorEvent::ConnectionFailed {
+                        endpoint,
+                        reason,
+                        token,
+                    } => RecordedEvent::ConnectionFailed {
+                        endpoint: endpoint.to_string(),
+                        token,
+                        reason: format!("{:?}", reason),
+                    },
+                    ErrorEvent::SendFailed {
+                        endpoint,
+                        token,
+                        reason,
+                    } => RecordedEvent::SendFailed {
+                        endpoint: endpoint.to_string(),
+                        token,
+                        reason: format!("{:?}", reason),
+                    },
+                    ErrorEvent::ReceiveFailed { endpoint, reason } => {
+                        RecordedEvent::ReceiveFailed {
+                            endpoint: endpoint.to_string(),
+                            reason: format!("{:?}", reason),
+                        }
+                    }
+                    ErrorEvent::SocketError { endpoint, reason } => RecordedEvent::SocketError {
+                        endpoint: endpoint.to_string(),
+                        reason: format!("{:?}", reason),
+                    },
+                }
+            }
+            ChatAppEvent::Info(info) => RecordedEvent::Info(info),
+        }
+    }
+}
+
+impl RecordedEvent {
+    /// The message carried by the event, if any
+    pub fn message(&self) -> Option<&ChatMessage> {
+        match self {
+            RecordedEvent::Sending(msg)
+            | RecordedEvent::Sent(msg)
+            | RecordedEvent::Received(msg)
+            | RecordedEvent::AckSent(msg, _)
+            | RecordedEvent::AckReceived(msg) => Some(msg),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a recording
+#[derive(Serialize, Deserialize)]
+pub struct RecordedLine {
+    // since the start of the recording
+    pub offset_ms: u64,
+    pub wall_clock_ms: i64,
+    pub event: RecordedEvent,
+}
+
+/// First line of a recording
+#[derive(Serialize, Deserialize)]
+pub struct RecordingHeader {
+    // the peer whose events were recorded, not the one of the process replaying them
+    pub local_peer_uuid: String,
+}
+
+/// Writes every event reaching the handler to a JSON-lines file (`RECORD_PATH`), after
+/// a header line
+pub struct EventRecorder {
+    file: File,
+    start: Instant,
+}
+
+impl EventRecorder {
+    pub fn create(path: &str, local_peer_uuid: &str) -> Result<Self, String> {
+        let mut file =
+            File::create(path).map_err(|err| format!("Unable to create {}: {}", path, err))?;
+        let header = RecordingHeader {
+            local_peer_uuid: local_peer_uuid.to_string(),
+        };
+        let text = serde_json::to_string(&header).map_err(|err| err.to_string())?;
+        writeln!(file, "{}", text).map_err(|err| format!("Unable to write {}: {}", path, err))?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, event: &RecordedEvent) -> Result<(), String> {
+        let line = RecordedLine {
+            offset_ms: self.start.elapsed().as_millis() as u64,
+            wall_clock_ms: DTChatTime::now().timestamp_millis(),
+            event: event.clone(),
+        };
+        let text = serde_json::to_string(&line).map_err(|err| err.to_string())?;
+        writeln!(self.file, "{}", text).map_err(|err| err.to_string())
+    }
+}
+
+/// The readable lines of a recording
+pub struct Recording {
+    // None for a recording made before the header was written
+    pub local_peer_uuid: Option<String>,
+    pub lines: Vec<RecordedLine>,
+    // unreadable lines, left out of the replay
+    pub skipped_lines: usize,
+    pub first_error: Option<String>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}", path.display(), err))?;
+        Ok(Self::parse(&text))
+    }
+
+    /// A recording usually ends with a crash, a line cut by it does not hide the rest of
+    /// the file
+    fn parse(text: &str) -> Self {
+        let mut recording = Self {
+            local_peer_uuid: None,
+            lines: Vec::new(),
+            skipped_lines: 0,
+            first_error: None,
+        };
+        let mut first = true;
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if std::mem::take(&mut first) {
+                if let Ok(header) = serde_json::from_str::<RecordingHeader>(line) {
+                    recording.local_peer_uuid = Some(header.local_peer_uuid);
+                    continue;
+                }
+            }
+            match serde_json::from_str(line) {
+                Ok(line) => recording.lines.push(line),
+                Err(err) => {
+                    recording.skipped_lines += 1;
+                    if recording.first_error.is_none() {
+                        recording.first_error = Some(format!("line {}: {}", index + 1, err));
+                    }
+                }
+            }
+        }
+        recording
+    }
+
+    /// "2 unreadable lines skipped (line 40: ...)", None when every line was read
+    pub fn skipped_summary(&self) -> Option<String> {
+        let first_error = self.first_error.as_deref()?;
+        Some(format!(
+            "{} unreadable lines of the recording skipped ({})",
+            self.skipped_lines, first_error
+        ))
+    }
+}
+
+/// Feeds a recording to the handler from a background thread, keeping the recorded
+/// offsets divided by `speed`.
+pub fn spawn_replay(
+    lines: Vec<RecordedLine>,
+    handler: Arc<Mutex<EventHandler>>,
+    speed: f64,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let start = Instant::now();
+        for line in lines {
+            let due = Duration::from_secs_f64(line.offset_ms as f64 / 1000.0 / speed);
+            if let Some(wait) = due.checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+            let timestamp = DTChatTime::from_timestamp_millis(line.wall_clock_ms);
+            handler
+                .lock()
+                .unwrap()
+                .handle_event_at(line.event, timestamp);
+        }
+        handler
+            .lock()
+            .unwrap()
+            .handle_event(RecordedEvent::Info("Replay finished".to_string()));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info_line(offset_ms: u64, text: &str) -> String {
+        serde_json::to_string(&RecordedLine {
+            offset_ms,
+            wall_clock_ms: 1_700_000_000_000 + offset_ms as i64,
+            event: RecordedEvent::Info(text.to_string()),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn header_gives_the_local_peer() {
+        let text = format!("{{\"local_peer_uuid\":\"2\"}}\n{}\n", info_line(0, "first"));
+        let recording = Recording::parse(&text);
+        assert_eq!(recording.local_peer_uuid.as_deref(), Some("2"));
+        assert_eq!(recording.lines.len(), 1);
+        assert_eq!(recording.skipped_lines, 0);
+
+        // older recordings start with an event
+        let recording = Recording::parse(&info_line(0, "first"));
+        assert_eq!(recording.local_peer_uuid, None);
+        assert_eq!(recording.lines.len(), 1);
+    }
+
+    #[test]
+    fn truncated_last_line_is_skipped() {
+        let last = info_line(20, "cut by a crash");
+        let text = format!(
+            "{}\n{}\n{}",
+            info_line(0, "first"),
+            info_line(10, "second"),
+            &last[..last.len() / 2]
+        );
+        let recording = Recording::parse(&text);
+        assert_eq!(recording.lines.len(), 2);
+        assert_eq!(recording.lines[1].offset_ms, 10);
+        assert_eq!(recording.skipped_lines, 1);
+        assert!(recording.first_error.unwrap().starts_with("line 3:"));
+    }
+
+    #[test]
+    fn bad_lines_are_counted_and_empty_lines_ignored() {
+        let text = format!("not json\n\n{}\n{{}}\n", info_line(0, "kept"));
+        let recording = Recording::parse(&text);
+        assert_eq!(recording.lines.len(), 1);
+        assert_eq!(recording.skipped_lines, 2);
+        assert!(recording
+            .skipped_summary()
+            .unwrap()
+            .starts_with("2 unreadable"));
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::Local;
+use dtchat_backend::dtchat::Peer;
+use dtchat_backend::message::{ChatMessage, MessageStatus};
+use dtchat_backend::time::DTChatTime;
+use egui::Color32;
+
+use crate::analytics_view::{hand_off_time, one_way_delay, prediction_error};
+use crate::app::{DisplayEvent, EventLevel};
+use crate::main_view::MirroredData;
+use crate::messages::experiment::ExperimentLog;
+use crate::messages::export::status_name;
+use crate::utils::font::{pretty_duration, proto_name, StatusDisplayHelper};
+use crate::utils::stats::Summary;
+use crate::utils::uuid::safe_id_display;
+
+const TIMELINE_WIDTH: f64 = 1000.0;
+const TIMELINE_LANE_HEIGHT: f64 = 50.0;
+// room on the left for the peer names, at the bottom for the time axis
+const TIMELINE_LABEL_WIDTH: f64 = 120.0;
+const TIMELINE_AXIS_HEIGHT: f64 = 30.0;
+const TIMELINE_TICKS: usize = 6;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin-bottom:1.5em}\
+th,td{border:1px solid #ccc;padding:3px 8px;text-align:left;font-size:13px}\
+th{background:#eee}\
+.error{color:#c00}.debug{color:#888}\
+svg{border:1px solid #ccc;background:#fafafa}";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn hex_color(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+fn time_str(time: &DTChatTime) -> String {
+    time.ts_to_str(true, true, None, &Local)
+}
+
+fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut html = String::from("<table><tr>");
+    for header in headers {
+        html.push_str(&format!("<th>{}</th>", escape(header)));
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str(&format!("<td>{}</td>", escape(&cell)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn peer_name(data: &MirroredData, uuid: &str) -> String {
+    if uuid == data.local_peer.uuid {
+        return data.local_peer.name.clone();
+    }
+    match data.other_peers.get(uuid) {
+        Some(peer) => peer.name.clone(),
+        None => uuid.to_string(),
+    }
+}
+
+fn peers_section(data: &MirroredData) -> String {
+    let mut peers: Vec<&Peer> = data.other_peers.values().collect();
+    peers.sort_by(|a, b| a.name.cmp(&b.name));
+    let rows = std::iter::once(&data.local_peer)
+        .chain(peers)
+        .map(|peer| {
+            let endpoints = peer
+                .endpoints
+                .iter()
+                .map(|e| format!("{} {}", proto_name(&e.proto), e.endpoint))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let name = if peer.uuid == data.local_peer.uuid {
+                format!("{} (local)", peer.name)
+            } else {
+                peer.name.clone()
+            };
+            vec![name, peer.uuid.clone(), endpoints]
+        })
+        .collect();
+    format!(
+        "<h2>Peers</h2>\n{}",
+        table(&["name", "uuid", "endpoints"], rows)
+    )
+}
+
+fn rooms_section(data: &MirroredData) -> String {
+    let mut rooms: Vec<_> = data.rooms.values().collect();
+    rooms.sort_by(|a, b| a.name.cmp(&b.name));
+    let rows = rooms
+        .into_iter()
+        .map(|room| {
+            let participants = match data.room_participants.get(&room.uuid) {
+                Some(participants) => participants
+                    .iter()
+                    .map(|participant| peer_name(data, &participant.peer_uuid))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                None => "everyone".to_string(),
+            };
+            vec![room.name.clone(), room.uuid.clone(), participants]
+        })
+        .collect();
+    format!(
+        "<h2>Rooms</h2>\n{}",
+        table(&["name", "uuid", "participants"], rows)
+    )
+}
+
+fn status_section(messages: &[ChatMessage]) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for msg in messages {
+        *counts.entry(status_name(&msg.status)).or_insert(0) += 1;
+    }
+    let rows = counts
+        .into_iter()
+        .map(|(status, count)| vec![status.to_string(), count.to_string()])
+        .chain(std::iter::once(vec![
+            "total".to_string(),
+            messages.len().to_string(),
+        ]))
+        .collect();
+    format!(
+        "<h2>Messages by status</h2>\n{}",
+        table(&["status", "messages"], rows)
+    )
+}
+
+fn summary_row(name: &str, values: &[f64]) -> Vec<String> {
+    let secs = |value: f64| pretty_duration((value * 1000.0).round() as i64);
+    match Summary::of(values) {
+        Some(s) => vec![
+            name.to_string(),
+            s.count.to_string(),
+            secs(s.mean),
+            secs(s.min),
+            secs(s.p50),
+            secs(s.p90),
+            secs(s.p99),
+            secs(s.max),
+        ],
+        None => vec![name.to_string(), "0".to_string()],
+    }
+}
+
+fn latency_section(messages: &[ChatMessage]) -> String {
+    let delays: Vec<f64> = messages.iter().filter_map(one_way_delay).collect();
+    let hand_offs: Vec<f64> = messages.iter().filter_map(hand_off_time).collect();
+    let errors: Vec<f64> = messages
+        .iter()
+        .filter_map(prediction_error)
+        .map(|(error, _)| error)
+        .collect();
+    let abs_errors: Vec<f64> = errors.iter().map(|e| e.abs()).collect();
+    let rows = vec![
+        summary_row("one-way delay", &delays),
+        summary_row("hand-off", &hand_offs),
+        summary_row("prediction error", &errors),
+        summary_row("|prediction error|", &abs_errors),
+    ];
+    format!(
+        "<h2>Latency and prediction error</h2>\n\
+         <p>one-way delay: receive - send time, hand-off: send completed - send time, \
+         prediction error: receive - predicted arrival time (positive: late)</p>\n{}",
+        table(
+            &["", "samples", "mean", "min", "p50", "p90", "p99", "max"],
+            rows
+        )
+    )
+}
+
+/// One row per copy of the experiments whose messages are in `data`
+fn experiments_section(data: &MirroredData, experiments: &ExperimentLog) -> String {
+    let secs = |value: Option<f64>| {
+        value.map_or("-".to_string(), |value| {
+            pretty_duration((value * 1000.0).round() as i64)
+        })
+    };
+    let mut rows = Vec::new();
+    for group in experiments.groups() {
+        let copies: Vec<_> = group
+            .copies
+            .iter()
+            .map(|copy| {
+                let msg = copy
+                    .msg_uuid
+                    .as_ref()
+                    .and_then(|uuid| data.messages.iter().find(|msg| msg.uuid == *uuid));
+                (copy, msg)
+            })
+            .collect();
+        if copies.iter().all(|(_, msg)| msg.is_none()) {
+            continue;
+        }
+        let sent = DTChatTime::from_timestamp_millis(group.sent_ms)
+            .map(|time| time_str(&time))
+            .unwrap_or_default();
+        for (copy, msg) in copies {
+            rows.push(vec![
+                format!("#{}", group.id),
+                group.peer_name.clone(),
+                sent.clone(),
+                group.payload_str(),
+                copy.proto_str(),
+                copy.endpoint.address.clone(),
+                copy.msg_uuid
+                    .as_deref()
+                    .map(|uuid| safe_id_display(uuid).to_string())
+                    .unwrap_or_default(),
+                msg.map_or("not sent", |msg| status_name(&msg.status))
+                    .to_string(),
+                secs(msg.and_then(hand_off_time)),
+                secs(msg.and_then(one_way_delay)),
+            ]);
+        }
+    }
+    if rows.is_empty() {
+        return String::new();
+    }
+    format!(
+        "<h2>Experiments</h2>\n<p>the same payload sent to a peer over each of its \
+         endpoints</p>\n{}",
+        table(
+            &[
+                "experiment",
+                "peer",
+                "sent",
+                "payload",
+                "protocol",
+                "endpoint",
+                "message",
+                "status",
+                "hand-off",
+                "one-way delay",
+            ],
+            rows
+        )
+    )
+}
+
+/// Peers a message of the local peer to `room_uuid` is sent to: the peer of a direct
+/// message, the configured participants of a room, or every other peer when the room has
+/// no participant in the configuration
+fn recipient_uuids<'a>(data: &'a MirroredData, room_uuid: &'a str) -> Vec<&'a str> {
+    if !data.rooms.contains_key(room_uuid) {
+        return vec![room_uuid];
+    }
+    match data.room_participants.get(room_uuid) {
+        Some(participants) => participants
+            .iter()
+            .map(|participant| participant.peer_uuid.as_str())
+            .filter(|uuid| *uuid != data.local_peer.uuid)
+            .collect(),
+        None => data.other_peers.keys().map(String::as_str).collect(),
+    }
+}
+
+/// Messages on one lane per peer, from the send time on the sender lane to the receive
+/// time on the receiver lane
+fn timeline_svg(data: &MirroredData) -> String {
+    let mut others: Vec<&Peer> = data.other_peers.values().collect();
+    others.sort_by(|a, b| a.name.cmp(&b.name));
+    let lanes: Vec<&Peer> = std::iter::once(&data.local_peer).chain(others).collect();
+    let lane_y = |uuid: &str| {
+        lanes
+            .iter()
+            .position(|peer| peer.uuid == uuid)
+            .map(|idx| (idx as f64 + 0.5) * TIMELINE_LANE_HEIGHT)
+    };
+
+    let times = data.messages.iter().flat_map(|msg| {
+        std::iter::once(msg.send_time.timestamp_millis())
+            .chain(msg.receive_time.map(|t| t.timestamp_millis()))
+    });
+    let (Some(start), Some(end)) = (times.clone().min(), times.max()) else {
+        return "<p>No message</p>".to_string();
+    };
+    let span = (end - start).max(1) as f64;
+    let plot_width = TIMELINE_WIDTH - TIMELINE_LABEL_WIDTH - 20.0;
+    let x = |ms: i64| TIMELINE_LABEL_WIDTH + (ms - start) as f64 / span * plot_width;
+    let height = lanes.len() as f64 * TIMELINE_LANE_HEIGHT + TIMELINE_AXIS_HEIGHT;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-size=\"11\">\n",
+        TIMELINE_WIDTH, height
+    );
+    for (idx, peer) in lanes.iter().enumerate() {
+        let y = (idx as f64 + 0.5) * TIMELINE_LANE_HEIGHT;
+        let color = data
+            .peer_colors
+            .get(&peer.uuid)
+            .copied()
+            .unwrap_or(Color32::DARK_GRAY);
+        svg.push_str(&format!(
+            "<text x=\"5\" y=\"{:.1}\" fill=\"{}\">{}</text>\
+             <line x1=\"{}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"#ccc\"/>\n",
+            y + 4.0,
+            hex_color(color),
+            escape(&peer.name),
+            TIMELINE_LABEL_WIDTH,
+            y,
+            TIMELINE_WIDTH - 20.0,
+            y
+        ));
+    }
+    let axis_y = lanes.len() as f64 * TIMELINE_LANE_HEIGHT;
+    for tick in 0..TIMELINE_TICKS {
+        let ms = start + ((end - start) as f64 * tick as f64 / (TIMELINE_TICKS - 1) as f64) as i64;
+        let label = DTChatTime::from_timestamp_millis(ms)
+            .map(|t| t.ts_to_str(false, true, None, &Local))
+            .unwrap_or_default();
+        svg.push_str(&format!(
+            "<line x1=\"{0:.1}\" y1=\"0\" x2=\"{0:.1}\" y2=\"{1}\" stroke=\"#eee\"/>\
+             <text x=\"{0:.1}\" y=\"{2}\" text-anchor=\"middle\">{3}</text>\n",
+            x(ms),
+            axis_y,
+            axis_y + 18.0,
+            escape(&label)
+        ));
+    }
+
+    for msg in &data.messages {
+        let Some(from_y) = lane_y(&msg.sender_uuid) else {
+            continue;
+        };
+        let color = hex_color(msg.status.get_color());
+        let send_x = x(msg.send_time.timestamp_millis());
+        let tooltip = format!(
+            "{} \u{2192} {} ({}, {}): {}",
+            peer_name(data, &msg.sender_uuid),
+            peer_name(data, &msg.room_uuid),
+            proto_name(&msg.source_endpoint.proto),
+            status_name(&msg.status),
+            msg.content_as_string()
+        );
+        // sent messages go to the lane of each recipient, received ones to the local lane
+        let recipients = if msg.sender_uuid == data.local_peer.uuid {
+            recipient_uuids(data, &msg.room_uuid)
+        } else {
+            vec![data.local_peer.uuid.as_str()]
+        };
+        if let Some(receive) = msg.receive_time {
+            for to_y in recipients.into_iter().filter_map(lane_y) {
+                svg.push_str(&format!(
+                    "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" \
+                     stroke-width=\"1.5\"><title>{}</title></line>\n",
+                    send_x,
+                    from_y,
+                    x(receive.timestamp_millis()),
+                    to_y,
+                    color,
+                    escape(&tooltip)
+                ));
+            }
+        }
+        let fill = if msg.status == MessageStatus::Failed {
+            "#c00".to_string()
+        } else {
+            color
+        };
+        svg.push_str(&format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"4\" fill=\"{}\" stroke=\"#444\">\
+             <title>{}</title></circle>\n",
+            send_x,
+            from_y,
+            fill,
+            escape(&tooltip)
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn event_log_section(data: &MirroredData) -> String {
+    let mut events: Vec<(&str, &DisplayEvent)> = data
+        .app_events
+        .iter()
+        .map(|event| ("app", event))
+        .chain(data.network_events.iter().map(|event| ("network", event)))
+        .collect();
+    events.sort_by_key(|(_, event)| event.timestamp.timestamp_millis());
+
+    let mut html = format!("<h2>Event log</h2>\n<p>{} events", events.len());
+    if data.omitted_events > 0 {
+        html.push_str(&format!(", {} older events omitted", data.omitted_events));
+    }
+    html.push_str(
+        "</p>\n<table><tr><th>time</th><th>category</th>\
+         <th>level</th><th>event</th><th>message</th></tr>\n",
+    );
+    for (category, event) in events {
+        let class = match event.level {
+            EventLevel::Info => "info",
+            EventLevel::Debug => "debug",
+            EventLevel::Error => "error",
+        };
+        let message = match &event.message_uuid {
+            Some(uuid) => safe_id_display(uuid).to_string(),
+            None => String::new(),
+        };
+        html.push_str(&format!(
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            class,
+            escape(&time_str(&event.timestamp)),
+            category,
+            event.level.name(),
+            escape(&event.message),
+            escape(&message)
+        ));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Single HTML file without external resources describing the session of `data`
+pub fn session_report(data: &MirroredData, experiments: &ExperimentLog) -> String {
+    let title = format!("DTChat session report - {}", data.local_peer.name);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title>\
+         <style>{1}</style></head><body>\n<h1>{0}</h1>\n<p>Generated on {2}</p>\n",
+        escape(&title),
+        STYLE,
+        escape(&time_str(&DTChatTime::now()))
+    );
+    html.push_str(&peers_section(data));
+    html.push_str(&rooms_section(data));
+    html.push_str(&status_section(&data.messages));
+    html.push_str(&latency_section(&data.messages));
+    html.push_str(&experiments_section(data, experiments));
+    html.push_str("<h2>Timeline</h2>\n");
+    html.push_str(&timeline_svg(data));
+    html.push_str(&event_log_section(data));
+    html.push_str("</body></html>\n");
+    html
+}
+
+pub fn write_report(
+    path: &Path,
+    data: &MirroredData,
+    experiments: &ExperimentLog,
+) -> Result<(), String> {
+    fs::write(path, session_report(data, experiments))
+        .map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+}
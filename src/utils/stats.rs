@@ -0,0 +1,107 @@
+/// Descriptive statistics of a sample
+#[derive(Clone, Debug)]
+pub struct Summary {
+    pub count: usize,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+/// Linear interpolation between the closest ranks, `sorted` must be sorted and not empty
+pub fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = (p / 100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let high = rank.ceil() as usize;
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+impl Summary {
+    pub fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        Some(Self {
+            count: sorted.len(),
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+        })
+    }
+}
+
+/// Bins of equal width over [min, max]: (start of the bin, count)
+pub fn histogram(values: &[f64], min: f64, max: f64, bins: usize) -> Vec<(f64, usize)> {
+    let width = bin_width(min, max, bins);
+    let mut counts = vec![0; bins];
+    for value in values {
+        let idx = ((value - min) / width).floor() as usize;
+        counts[idx.min(bins - 1)] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(idx, count)| (min + idx as f64 * width, count))
+        .collect()
+}
+
+pub fn bin_width(min: f64, max: f64, bins: usize) -> f64 {
+    if max > min {
+        (max - min) / bins as f64
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_interpolates_between_ranks() {
+        let sorted = [10.0, 20.0, 30.0, 40.0];
+        assert_eq!(percentile(&sorted, 0.0), 10.0);
+        assert_eq!(percentile(&sorted, 50.0), 25.0);
+        assert_eq!(percentile(&sorted, 100.0), 40.0);
+        // out of range ranks are clamped
+        assert_eq!(percentile(&sorted, 150.0), 40.0);
+        assert_eq!(percentile(&[7.0], 90.0), 7.0);
+    }
+
+    #[test]
+    fn summary_of_unsorted_values() {
+        let summary = Summary::of(&[3.0, 1.0, 2.0, 10.0]).unwrap();
+        assert_eq!(summary.count, 4);
+        assert_eq!(summary.mean, 4.0);
+        assert_eq!(summary.min, 1.0);
+        assert_eq!(summary.max, 10.0);
+        assert_eq!(summary.p50, 2.5);
+        assert!((summary.p90 - 7.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn summary_of_nothing() {
+        assert!(Summary::of(&[]).is_none());
+    }
+
+    #[test]
+    fn histogram_counts_each_value_once() {
+        let bins = histogram(&[0.0, 1.0, 4.9, 5.0, 10.0], 0.0, 10.0, 2);
+        // the maximum falls in the last bin
+        assert_eq!(bins, vec![(0.0, 3), (5.0, 2)]);
+    }
+
+    #[test]
+    fn histogram_of_identical_values() {
+        let bins = histogram(&[2.0, 2.0], 2.0, 2.0, 3);
+        assert_eq!(bin_width(2.0, 2.0, 3), 1.0);
+        assert_eq!(bins, vec![(2.0, 2), (3.0, 0), (4.0, 0)]);
+    }
+}
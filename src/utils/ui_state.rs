@@ -0,0 +1,175 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::messages::delivery::SentVia;
+use crate::messages::experiment::ExperimentGroup;
+use crate::messages::retry::RetryLink;
+use crate::messages::smart_view::{SavedMode, SmartView};
+use crate::messages::SavedPreferences;
+use crate::utils::journal::journal_dir;
+
+/// Conversation shown when the state was saved, with the peer and room remembered by the
+/// side panel
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SavedConversation {
+    pub mode: SavedMode,
+    #[serde(default)]
+    pub last_peer: Option<String>,
+    #[serde(default)]
+    pub last_room: Option<String>,
+}
+
+/// What the GUI keeps between sessions, one sidecar file per local peer
+/// (`ui-state-<peer>.yaml`, next to the journals). Missing values keep the defaults of the
+/// views.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct UiState {
+    pub smart_views: Vec<SmartView>,
+    pub main_view: Option<String>,
+    pub message_view: Option<String>,
+    pub conversation: Option<SavedConversation>,
+    // context uuid (peer, room or "All") -> preferences
+    pub preferences: BTreeMap<String, SavedPreferences>,
+    pub current_context: Option<String>,
+    // conversation uuid -> arrival time of the last message read, ms
+    pub last_read: BTreeMap<String, i64>,
+    // last-read marker of the conversations never opened
+    pub unread_since: Option<i64>,
+    // peers and rooms without toasts
+    pub muted: BTreeSet<String>,
+    // retries in sending order, the model does not know them
+    pub retry_links: Vec<RetryLink>,
+    // message uuid -> endpoint and delivery policy of the direct messages sent
+    pub sent_via: BTreeMap<String, SentVia>,
+    pub experiments: Vec<ExperimentGroup>,
+    pub do_not_disturb: bool,
+    pub window_size: Option<[f32; 2]>,
+    pub side_panel_width: Option<f32>,
+    pub inspector_width: Option<f32>,
+    pub graph_auto_bounds: Option<bool>,
+    pub graph_show_contacts: Option<bool>,
+    pub sequence_auto_bounds: Option<bool>,
+    pub list_show_timestamps: Option<bool>,
+}
+
+impl UiState {
+    pub fn path(local_peer_uuid: &str) -> PathBuf {
+        journal_dir().join(format!("ui-state-{}.yaml", local_peer_uuid))
+    }
+
+    /// `ui-state-<peer>.yaml.bak`
+    fn backup_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".bak");
+        PathBuf::from(name)
+    }
+
+    /// The default state when the file does not exist yet. An invalid file is copied to
+    /// `<file>.bak` before the defaults are used. Err if the file could not be read or that
+    /// copy failed: the file must then be left as it is.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(format!("Unable to read {}: {}", path.display(), err)),
+        };
+        match serde_yaml::from_str(&text) {
+            Ok(state) => Ok(state),
+            Err(err) => {
+                elog_with_location!("Unable to parse {}: {}", path.display(), err);
+                let backup = Self::backup_path(path);
+                fs::copy(path, &backup).map_err(|err| {
+                    format!(
+                        "Unable to back up {} to {}: {}",
+                        path.display(),
+                        backup.display(),
+                        err
+                    )
+                })?;
+                elog_with_location!("Invalid UI state kept in {}", backup.display());
+                Ok(Self::default())
+            }
+        }
+    }
+
+    /// Written to a temporary file renamed over the previous state, which is never left
+    /// half written
+    fn write(path: &Path, text: &str) -> Result<(), String> {
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+        fs::write(&tmp_path, text)
+            .map_err(|err| format!("Unable to write {}: {}", tmp_path.display(), err))?;
+        fs::rename(&tmp_path, path).map_err(|err| {
+            format!(
+                "Unable to replace {} with {}: {}",
+                path.display(),
+                tmp_path.display(),
+                err
+            )
+        })
+    }
+}
+
+/// Serializes and writes the UI states on its own thread, a state equal to the last one
+/// written is skipped. When several states are waiting, only the latest is written.
+pub struct UiStateWriter {
+    sender: Option<mpsc::Sender<UiState>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl UiStateWriter {
+    pub fn new(path: PathBuf) -> Self {
+        let (sender, receiver) = mpsc::channel::<UiState>();
+        let thread = thread::spawn(move || {
+            let mut written = String::new();
+            while let Ok(mut state) = receiver.recv() {
+                while let Ok(next) = receiver.try_recv() {
+                    state = next;
+                }
+                let text = match serde_yaml::to_string(&state) {
+                    Ok(text) => text,
+                    Err(err) => {
+                        elog_with_location!("Unable to serialize the UI state: {}", err);
+                        continue;
+                    }
+                };
+                if text == written {
+                    continue;
+                }
+                match UiState::write(&path, &text) {
+                    Ok(()) => written = text,
+                    Err(err) => elog_with_location!("{}", err),
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    pub fn save(&self, state: UiState) {
+        if let Some(sender) = &self.sender {
+            // the thread only stops once the sender is dropped
+            let _ = sender.send(state);
+        }
+    }
+
+    /// Waits for the states already given to be written
+    pub fn finish(&mut self) {
+        self.sender = None;
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                elog_with_location!("The UI state writer panicked");
+            }
+        }
+    }
+}